@@ -6,11 +6,13 @@ mod service;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let io = (tokio::io::stdin(), tokio::io::stdout());
-    service::DiveDefaultService::new()
-        .serve(io)
-        .await?
-        .waiting()
-        .await?;
+    let service = service::DiveDefaultService::new(service::ServiceConfig::from_env());
+    let running = service.clone().serve(io).await?;
+    tokio::spawn(service.shutdown_when_idle(running.cancellation_token()));
+    if let rmcp::service::QuitReason::Cancelled = running.waiting().await? {
+        // the blocking read on stdin would otherwise keep the runtime from shutting down
+        std::process::exit(0);
+    }
 
     Ok(())
 }
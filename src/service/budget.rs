@@ -0,0 +1,117 @@
+use rmcp::model::{CallToolResult, Content, RawContent};
+
+use crate::service::DiveDefaultService;
+
+/// Note appended to a truncated result
+fn truncation_marker(max_chars: usize, total: usize) -> String {
+    format!(
+        "\n\n[Output truncated: showing {} of {} characters. Narrow the request, e.g. with \
+         an offset, limit or page parameter, to retrieve the rest.]",
+        max_chars, total
+    )
+}
+
+/// Shorten the strings of a JSON value in document order until they hold at most `remaining`
+/// characters together; later strings are emptied. The shape of the value never changes, so
+/// it keeps matching its schema.
+fn truncate_strings(value: &mut serde_json::Value, remaining: &mut usize) {
+    match value {
+        serde_json::Value::String(text) => {
+            if let Some((end, _)) = text.char_indices().nth(*remaining) {
+                text.truncate(end);
+            }
+            *remaining = remaining.saturating_sub(text.chars().count());
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                truncate_strings(item, remaining);
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for field in fields.values_mut() {
+                truncate_strings(field, remaining);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl DiveDefaultService {
+    /// Truncate a tool result to the configured output budget, so a single call cannot flood
+    /// the client's context. Structured content is measured as serialized JSON and cut down by
+    /// shortening its strings, so it still matches the tool's output schema; when that cannot
+    /// make it fit, only the truncated text is returned.
+    pub fn apply_output_budget(&self, mut result: CallToolResult) -> CallToolResult {
+        let Some(max_chars) = self.config.max_output_chars else {
+            return result;
+        };
+        if let Some(structured) = result.structured_content.take() {
+            let serialized = structured.to_string();
+            let total = serialized.chars().count();
+            if total <= max_chars {
+                result.structured_content = Some(structured);
+                return result;
+            }
+            // Budget the strings by what the rest of the document leaves over, then shrink
+            // further by whatever escaping added
+            let mut empty = structured.clone();
+            truncate_strings(&mut empty, &mut 0);
+            let mut budget = max_chars.saturating_sub(empty.to_string().chars().count());
+            while budget > 0 {
+                let mut truncated = structured.clone();
+                truncate_strings(&mut truncated, &mut budget.clone());
+                let shortened = truncated.to_string();
+                let length = shortened.chars().count();
+                if length <= max_chars {
+                    result.content = vec![
+                        Content::text(shortened),
+                        Content::text(truncation_marker(max_chars, total)),
+                    ];
+                    result.structured_content = Some(truncated);
+                    return result;
+                }
+                budget = budget.saturating_sub(length - max_chars);
+            }
+            // Too many items to fit even with empty strings, so fall back to truncated text
+            result.content = vec![Content::text(serialized)];
+        }
+
+        let total: usize = result
+            .content
+            .iter()
+            .filter_map(|content| content.as_text())
+            .map(|text| text.text.chars().count())
+            .sum();
+        if total <= max_chars {
+            return result;
+        }
+
+        let mut remaining = max_chars;
+        result.content.retain_mut(|content| {
+            let RawContent::Text(text) = &mut content.raw else {
+                return true;
+            };
+            if remaining == 0 {
+                return false;
+            }
+            if let Some((end, _)) = text.text.char_indices().nth(remaining) {
+                text.text.truncate(end);
+            }
+            remaining = remaining.saturating_sub(text.text.chars().count());
+            true
+        });
+        let marker = truncation_marker(max_chars, total);
+        match result
+            .content
+            .iter_mut()
+            .rev()
+            .find_map(|content| match &mut content.raw {
+                RawContent::Text(text) => Some(text),
+                _ => None,
+            }) {
+            Some(text) => text.text.push_str(&marker),
+            None => result.content.push(Content::text(marker)),
+        }
+        result
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Runtime settings of the service, read from `DIVE_MCP_*` environment variables
+#[derive(Debug, Clone)]
+pub struct ServiceConfig {
+    /// Maximum number of entries `list_directory` returns per call
+    pub max_list_entries: usize,
+    /// Number of items `batch_fetch` and `read_multiple_files` process at once when a call
+    /// does not set its own `concurrency`; a per-call value always takes precedence
+    pub default_concurrency: usize,
+    /// Instructions advertised to clients in the server info
+    pub instructions: String,
+    /// Server name advertised to clients, instead of the built-in one
+    pub server_name: Option<String>,
+    /// Server version advertised to clients, instead of the built-in one
+    pub server_version: Option<String>,
+    /// Whether `read_file`, `stat_file` and `list_directory` follow symlinks; when false they
+    /// describe the link itself and reading a link returns its target path
+    pub follow_symlinks: bool,
+    /// Hold an advisory exclusive lock on files while `write_file` writes them
+    pub lock_writes: bool,
+    /// How long a locked write waits for another holder to release the file, in milliseconds
+    pub lock_timeout_ms: u64,
+    /// Randomize the backoff between fetch retries so that clients failing together spread
+    /// their retries out; a server's Retry-After is always honored as given
+    pub retry_jitter: bool,
+    /// Maximum number of requests the fetch tools send to a single host at once; unlimited
+    /// when unset
+    pub max_connections_per_host: Option<usize>,
+    /// Hold back calls to destructive tools until they are confirmed with `confirm_action`
+    pub require_confirmation: bool,
+    /// How long a held back call can be confirmed, in seconds
+    pub confirmation_ttl_secs: u64,
+    /// Shut the server down after this many seconds without tool calls; never when unset
+    pub idle_timeout_secs: Option<u64>,
+    /// Files larger than this many bytes are summarized by `read_file` and
+    /// `read_multiple_files` instead of returned whole; unlimited when unset
+    pub max_read_bytes: Option<u64>,
+    /// Number of lines kept from the start and from the end of a summarized file
+    pub summary_lines: usize,
+    /// Maximum number of calls a minute per tool name, such as `delete_file=100`; tools not
+    /// listed are unlimited
+    pub tool_quotas: HashMap<String, usize>,
+    /// Maximum number of characters of text a tool result returns; longer results are
+    /// truncated with a marker, structured results by shortening their strings. Unlimited
+    /// when unset.
+    pub max_output_chars: Option<usize>,
+    /// Refuse writes of the tools that store bulk data, such as `write_file`, `copy_file` and
+    /// `download_file`, that would leave less than this many bytes available on the
+    /// filesystem; never refused when unset
+    pub min_free_bytes: Option<u64>,
+    /// URL of the search API `web_search` queries, with `{query}` and optionally `{count}`
+    /// placeholders, e.g. `https://search.example.com/api?q={query}&limit={count}`; the tool
+    /// fails when unset
+    pub search_endpoint: Option<String>,
+    /// API key sent with search requests
+    pub search_api_key: Option<String>,
+    /// Header carrying the search API key; the key is sent as a bearer token when this is
+    /// `Authorization`
+    pub search_api_key_header: String,
+    /// JSONPath of the result array in search responses
+    pub search_results_path: String,
+    /// Fields of a search result holding its title, url and snippet, such as
+    /// `url=link,snippet=description`, for providers that name them differently
+    pub search_fields: HashMap<String, String>,
+    /// Time limit of calls per tool name in milliseconds, such as `fetch=30000,read_file=5000`
+    pub tool_timeouts_ms: HashMap<String, u64>,
+    /// Time limit in milliseconds of calls to tools without their own timeout; unlimited
+    /// when unset
+    pub default_tool_timeout_ms: Option<u64>,
+    /// Directory `mirror_url` keeps its downloaded copies in
+    pub cache_dir: PathBuf,
+    /// Directory `archive_file` files documents under, in `archive/YYYY/MM/`; when unset they
+    /// are archived next to the file
+    pub archive_root: Option<PathBuf>,
+}
+
+impl Default for ServiceConfig {
+    fn default() -> Self {
+        Self {
+            max_list_entries: 10_000,
+            default_concurrency: 4,
+            instructions: "default mcp server for dive client".to_string(),
+            server_name: None,
+            server_version: None,
+            follow_symlinks: true,
+            lock_writes: false,
+            lock_timeout_ms: 5_000,
+            retry_jitter: true,
+            max_connections_per_host: None,
+            require_confirmation: false,
+            confirmation_ttl_secs: 300,
+            idle_timeout_secs: None,
+            max_read_bytes: None,
+            summary_lines: 50,
+            tool_quotas: HashMap::new(),
+            max_output_chars: None,
+            min_free_bytes: None,
+            search_endpoint: None,
+            search_api_key: None,
+            search_api_key_header: "Authorization".to_string(),
+            search_results_path: "$.results".to_string(),
+            search_fields: HashMap::new(),
+            tool_timeouts_ms: HashMap::new(),
+            default_tool_timeout_ms: None,
+            cache_dir: std::env::temp_dir().join("dive-mcp-cache"),
+            archive_root: None,
+        }
+    }
+}
+
+impl ServiceConfig {
+    /// Build the config from the environment, falling back to defaults for unset or invalid values
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_list_entries: env_parse("DIVE_MCP_MAX_LIST_ENTRIES")
+                .unwrap_or(default.max_list_entries),
+            default_concurrency: env_parse("DIVE_MCP_DEFAULT_CONCURRENCY")
+                .unwrap_or(default.default_concurrency),
+            instructions: env_parse("DIVE_MCP_INSTRUCTIONS").unwrap_or(default.instructions),
+            server_name: env_parse("DIVE_MCP_SERVER_NAME"),
+            server_version: env_parse("DIVE_MCP_SERVER_VERSION"),
+            follow_symlinks: env_parse("DIVE_MCP_FOLLOW_SYMLINKS")
+                .unwrap_or(default.follow_symlinks),
+            lock_writes: env_parse("DIVE_MCP_LOCK_WRITES").unwrap_or(default.lock_writes),
+            lock_timeout_ms: env_parse("DIVE_MCP_LOCK_TIMEOUT_MS")
+                .unwrap_or(default.lock_timeout_ms),
+            retry_jitter: env_parse("DIVE_MCP_RETRY_JITTER").unwrap_or(default.retry_jitter),
+            max_connections_per_host: env_parse("DIVE_MCP_MAX_CONNECTIONS_PER_HOST"),
+            require_confirmation: env_parse("DIVE_MCP_REQUIRE_CONFIRMATION")
+                .unwrap_or(default.require_confirmation),
+            confirmation_ttl_secs: env_parse("DIVE_MCP_CONFIRMATION_TTL_SECS")
+                .unwrap_or(default.confirmation_ttl_secs),
+            idle_timeout_secs: env_parse("DIVE_MCP_IDLE_TIMEOUT_SECS"),
+            max_read_bytes: env_parse("DIVE_MCP_MAX_READ_BYTES"),
+            summary_lines: env_parse("DIVE_MCP_SUMMARY_LINES").unwrap_or(default.summary_lines),
+            tool_quotas: env_parse_map("DIVE_MCP_TOOL_QUOTAS").unwrap_or(default.tool_quotas),
+            max_output_chars: env_parse("DIVE_MCP_MAX_OUTPUT_CHARS"),
+            min_free_bytes: env_parse("DIVE_MCP_MIN_FREE_BYTES"),
+            search_endpoint: env_parse("DIVE_MCP_SEARCH_ENDPOINT"),
+            search_api_key: env_parse("DIVE_MCP_SEARCH_API_KEY"),
+            search_api_key_header: env_parse("DIVE_MCP_SEARCH_API_KEY_HEADER")
+                .unwrap_or(default.search_api_key_header),
+            search_results_path: env_parse("DIVE_MCP_SEARCH_RESULTS_PATH")
+                .unwrap_or(default.search_results_path),
+            search_fields: env_parse_map("DIVE_MCP_SEARCH_FIELDS").unwrap_or(default.search_fields),
+            tool_timeouts_ms: env_parse_map("DIVE_MCP_TOOL_TIMEOUTS_MS")
+                .unwrap_or(default.tool_timeouts_ms),
+            default_tool_timeout_ms: env_parse("DIVE_MCP_DEFAULT_TOOL_TIMEOUT_MS"),
+            cache_dir: env_parse("DIVE_MCP_CACHE_DIR").unwrap_or(default.cache_dir),
+            archive_root: env_parse("DIVE_MCP_ARCHIVE_ROOT"),
+        }
+    }
+}
+
+fn env_parse<T: FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok()?.parse().ok()
+}
+
+/// Parse a comma-separated list of `name=value` pairs, skipping pairs that do not parse
+fn env_parse_map<T: FromStr>(key: &str) -> Option<HashMap<String, T>> {
+    let raw = std::env::var(key).ok()?;
+    Some(
+        raw.split(',')
+            .filter_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                Some((name.trim().to_string(), value.trim().parse().ok()?))
+            })
+            .collect(),
+    )
+}
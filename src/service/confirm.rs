@@ -0,0 +1,129 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use rmcp::{
+    ErrorData as McpError, RoleServer,
+    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
+    model::{CallToolRequestParam, CallToolResult, Content},
+    service::RequestContext,
+    tool, tool_router,
+};
+use serde::Deserialize;
+
+use crate::service::DiveDefaultService;
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ConfirmActionParams {
+    /// The token returned by the tool call awaiting confirmation
+    token: String,
+}
+
+/// A destructive tool call held back until it is confirmed
+struct PendingAction {
+    request: CallToolRequestParam,
+    expires_at: Instant,
+}
+
+/// Destructive tool calls awaiting confirmation, keyed by token
+#[derive(Default)]
+pub struct PendingActions {
+    actions: Mutex<HashMap<String, PendingAction>>,
+}
+
+impl PendingActions {
+    /// Hold a call back for `ttl`, returning the token that confirms it
+    fn hold(&self, request: CallToolRequestParam, ttl: Duration) -> String {
+        let token = format!("{:032x}", rand::random::<u128>());
+        if let Ok(mut actions) = self.actions.lock() {
+            let now = Instant::now();
+            actions.retain(|_, action| action.expires_at > now);
+            actions.insert(
+                token.clone(),
+                PendingAction {
+                    request,
+                    expires_at: now + ttl,
+                },
+            );
+        }
+        token
+    }
+
+    /// Remove the call held back under a token, unless it expired
+    fn take(&self, token: &str) -> Option<CallToolRequestParam> {
+        let action = self.actions.lock().ok()?.remove(token)?;
+        (action.expires_at > Instant::now()).then_some(action.request)
+    }
+}
+
+/// Names of the tools explicitly annotated as destructive, collected once when the service
+/// is built
+pub fn destructive_tools(tool_router: &ToolRouter<DiveDefaultService>) -> HashSet<String> {
+    tool_router
+        .list_all()
+        .into_iter()
+        .filter(|tool| {
+            tool.annotations
+                .as_ref()
+                .and_then(|annotations| annotations.destructive_hint)
+                .unwrap_or(false)
+        })
+        .map(|tool| tool.name.into_owned())
+        .collect()
+}
+
+impl DiveDefaultService {
+    /// Whether a call has to wait for `confirm_action` before it runs. Only tools explicitly
+    /// annotated as destructive are held back, and only when the service requires confirmation.
+    pub fn requires_confirmation(&self, name: &str) -> bool {
+        self.config.require_confirmation
+            && name != "confirm_action"
+            && self.destructive_tools.contains(name)
+    }
+
+    /// Hold a call back and describe how to confirm it
+    pub fn hold_for_confirmation(&self, request: CallToolRequestParam) -> CallToolResult {
+        let ttl = Duration::from_secs(self.config.confirmation_ttl_secs);
+        let tool = request.name.clone();
+        let arguments = request.arguments.clone();
+        let token = self.pending_actions.hold(request, ttl);
+
+        let result = serde_json::json!({
+            "confirmationRequired": true,
+            "token": token,
+            "tool": tool,
+            "arguments": arguments,
+            "expiresInSeconds": ttl.as_secs(),
+            "message": format!("{} was not run yet; call confirm_action with the token to run it", tool),
+        });
+        CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )])
+    }
+}
+
+#[tool_router(router = tool_router_confirm, vis = "pub")]
+impl DiveDefaultService {
+    #[tool(
+        description = "Run a destructive tool call that was held back for confirmation, given its token",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn confirm_action(
+        &self,
+        Parameters(params): Parameters<ConfirmActionParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let Some(request) = self.pending_actions.take(&params.token) else {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "Unknown or expired confirmation token".to_string(),
+                None,
+            ));
+        };
+        self.quotas.check(&request.name)?;
+        // Run the held call directly, past the confirmation gate in call_tool
+        self.call_with_timeout(request, context).await
+    }
+}
@@ -0,0 +1,1130 @@
+use base64::{Engine as _, engine::general_purpose};
+use rmcp::{
+    ErrorData as McpError,
+    handler::server::wrapper::Parameters,
+    model::{CallToolResult, Content},
+    tool, tool_router,
+};
+use serde::Deserialize;
+use tokio::{
+    fs,
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+};
+
+use crate::service::{DiveDefaultService, fs::replace_file_atomically};
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ReadCsvParams {
+    /// The path to the CSV file to read
+    path: String,
+    /// Index of the first data row to return, not counting the header (default 0)
+    #[serde(default)]
+    start_row: Option<usize>,
+    /// Maximum number of rows to return (default 100)
+    #[serde(default)]
+    max_rows: Option<usize>,
+    /// Field delimiter, a single character (default ",")
+    #[serde(default)]
+    delimiter: Option<String>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct AppendCsvParams {
+    /// The path to the CSV file to append to, created with a header row when missing
+    path: String,
+    /// A JSON object, or an array of objects, each appended as one row; their keys must match
+    /// the header of an existing file
+    rows: serde_json::Value,
+    /// Field delimiter, a single character (default ",")
+    #[serde(default)]
+    delimiter: Option<String>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct WriteJsonParams {
+    /// The path to the JSON file to write
+    path: String,
+    /// The JSON value to write
+    value: serde_json::Value,
+    /// Deep-merge the value into the JSON object already in the file (default false)
+    #[serde(default)]
+    merge: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ReadDotenvParams {
+    /// The path to the .env file to read
+    path: String,
+    /// Replace the values of secret-looking keys with "***" (default false)
+    #[serde(default)]
+    redact_secrets: Option<bool>,
+    /// Regex matched against keys to decide which values are secret
+    /// (default matches keys containing secret, password, token, key, private or credential)
+    #[serde(default)]
+    secret_pattern: Option<String>,
+}
+
+/// Keys whose values `read_dotenv` redacts when no pattern is given
+const DEFAULT_SECRET_PATTERN: &str = r"(?i)(secret|passw(or)?d|token|key|private|credential)";
+
+/// Parse the value side of a `KEY=value` line, handling quotes and trailing comments
+fn parse_dotenv_value(raw: &str) -> String {
+    let raw = raw.trim();
+    if let Some(inner) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(other) => value.push(other),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        value
+    } else if let Some(inner) = raw.strip_prefix('\'') {
+        // Single quotes are literal
+        inner.split('\'').next().unwrap_or_default().to_string()
+    } else {
+        match raw.find(" #") {
+            Some(comment) => raw[..comment].trim_end().to_string(),
+            None => raw.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Guess the format of a file from its extension
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = std::path::Path::new(path).extension()?.to_str()?;
+        match extension.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Parse config content of the given format into a JSON value
+pub fn parse_config(content: &str, format: ConfigFormat) -> Result<serde_json::Value, String> {
+    match format {
+        ConfigFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+        ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+        ConfigFormat::Toml => toml::from_str(content)
+            .map(toml_to_json)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Convert a TOML value to JSON, writing dates and times as RFC 3339 strings rather than the
+/// private wrapper object serde gives them
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(text) => serde_json::Value::String(text),
+        toml::Value::Integer(number) => number.into(),
+        toml::Value::Float(number) => serde_json::Number::from_f64(number)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        toml::Value::Boolean(flag) => serde_json::Value::Bool(flag),
+        toml::Value::Datetime(datetime) => serde_json::Value::String(datetime.to_string()),
+        toml::Value::Array(items) => items.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => table
+            .into_iter()
+            .map(|(key, value)| (key, toml_to_json(value)))
+            .collect(),
+    }
+}
+
+/// Serialize a JSON value as config content of the given format
+pub fn render_config(value: &serde_json::Value, format: ConfigFormat) -> Result<String, String> {
+    match format {
+        ConfigFormat::Json => serde_json::to_string_pretty(value)
+            .map(|content| content + "\n")
+            .map_err(|e| e.to_string()),
+        ConfigFormat::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+        ConfigFormat::Toml => toml::to_string_pretty(value).map_err(|e| e.to_string()),
+    }
+}
+
+/// Whether YAML or TOML content has a `#` outside a quoted string, which may start a comment.
+/// Quotes only open a string at the start of a value, so the apostrophe in `it's` does not;
+/// a file that ends inside a string is treated as having comments to be safe.
+fn has_hash_comment(content: &str) -> bool {
+    let mut quote = None;
+    let mut previous = '\n';
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some('"') if c == '\\' => {
+                chars.next();
+            }
+            // A doubled quote is an escaped quote in a single-quoted YAML string
+            Some('\'') if c == '\'' && chars.peek() == Some(&'\'') => {
+                chars.next();
+            }
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '#' => return true,
+            None if (c == '"' || c == '\'')
+                && (previous.is_whitespace() || "=:[{,-".contains(previous)) =>
+            {
+                quote = Some(c)
+            }
+            None => {}
+        }
+        previous = c;
+    }
+    quote.is_some()
+}
+
+/// Look up a value by a simple JSONPath of keys and indexes, such as `$.links.next` or
+/// `data.items[0].id`
+pub fn select_json_path<'a>(
+    value: &'a serde_json::Value,
+    path: &str,
+) -> Option<&'a serde_json::Value> {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut current = value;
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        let (key, indexes) = match segment.find('[') {
+            Some(bracket) => segment.split_at(bracket),
+            None => (segment, ""),
+        };
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indexes.split('[').skip(1) {
+            let index: usize = index.strip_suffix(']')?.parse().ok()?;
+            current = current.get(index)?;
+        }
+    }
+    Some(current)
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct FilterNdjsonParams {
+    /// The path to the newline-delimited JSON file to filter
+    path: String,
+    /// Field to match on, as a key or a simple JSONPath such as `$.request.status`. Without
+    /// it every record matches.
+    #[serde(default)]
+    field: Option<String>,
+    /// Only match records whose field equals this JSON value. Without it, records match
+    /// when the field is present and not null.
+    #[serde(default)]
+    equals: Option<serde_json::Value>,
+    /// Number of matching records to skip (default 0)
+    #[serde(default)]
+    offset: Option<usize>,
+    /// Maximum number of matching records to return (default 100)
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ConvertConfigParams {
+    /// The path to the config file to convert, instead of content
+    #[serde(default)]
+    path: Option<String>,
+    /// Inline config content to convert, instead of path
+    #[serde(default)]
+    content: Option<String>,
+    /// Format of the input (defaults to the format implied by the path extension)
+    #[serde(default)]
+    from: Option<ConfigFormat>,
+    /// Format to convert to
+    to: ConfigFormat,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct FormatFileParams {
+    /// The path to the JSON, YAML or TOML file to reformat in place
+    path: String,
+    /// Format of the file (defaults to the format implied by the path extension)
+    #[serde(default)]
+    format: Option<ConfigFormat>,
+    /// Spaces per indentation level of JSON files (default 2)
+    #[serde(default)]
+    indent: Option<usize>,
+    /// Only report whether the file would change, without writing it (default false)
+    #[serde(default)]
+    check: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum PatchFormat {
+    /// JSON Merge Patch (RFC 7386): an object merged into the document, null removes a key
+    MergePatch,
+    /// JSON Patch (RFC 6902): an array of add, remove, replace, move, copy and test operations
+    JsonPatch,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct PatchJsonParams {
+    /// The path to the JSON file to patch
+    path: String,
+    /// The patch to apply
+    patch: serde_json::Value,
+    /// Kind of patch (defaults to json_patch for an array and merge_patch otherwise)
+    #[serde(default)]
+    format: Option<PatchFormat>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct EncodeBase64Params {
+    /// Inline text to encode, instead of path
+    #[serde(default)]
+    content: Option<String>,
+    /// The path to a file whose bytes to encode, instead of content
+    #[serde(default)]
+    path: Option<String>,
+    /// Use the URL-safe alphabet (- and _ instead of + and /) (default false)
+    #[serde(default)]
+    url_safe: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct DecodeBase64Params {
+    /// The base64 data to decode; whitespace and line breaks are ignored
+    data: String,
+    /// Write the decoded bytes to this path instead of returning them as text
+    #[serde(default)]
+    output_path: Option<String>,
+    /// Use the URL-safe alphabet (- and _ instead of + and /) (default false)
+    #[serde(default)]
+    url_safe: Option<bool>,
+}
+
+/// The base64 engine for the alphabet a call asked for
+fn base64_engine(url_safe: Option<bool>) -> &'static general_purpose::GeneralPurpose {
+    if url_safe.unwrap_or(false) {
+        &general_purpose::URL_SAFE
+    } else {
+        &general_purpose::STANDARD
+    }
+}
+
+/// Recursively merge `value` into `target`; objects are merged key by key, anything else replaces
+fn merge_json(target: &mut serde_json::Value, value: serde_json::Value) {
+    match (target, value) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(value)) => {
+            for (key, value) in value {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
+/// The text of a JSON value in a CSV field; nested values are written as JSON
+fn csv_field(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Parse a single-byte delimiter parameter
+fn parse_delimiter(delimiter: Option<&str>) -> Result<u8, McpError> {
+    match delimiter {
+        None => Ok(b','),
+        Some(delimiter) if delimiter.len() == 1 => Ok(delimiter.as_bytes()[0]),
+        Some(_) => Err(McpError::new(
+            rmcp::model::ErrorCode::INVALID_PARAMS,
+            "Delimiter must be a single ASCII character".to_string(),
+            None,
+        )),
+    }
+}
+
+#[tool_router(router = tool_router_data, vis = "pub")]
+impl DiveDefaultService {
+    #[tool(
+        description = "Read rows of a CSV file as JSON objects keyed by the header row",
+        annotations(read_only_hint = true)
+    )]
+    async fn read_csv(
+        &self,
+        Parameters(params): Parameters<ReadCsvParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let delimiter = parse_delimiter(params.delimiter.as_deref())?;
+        let bytes = match fs::read(&params.path).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .from_reader(bytes.as_slice());
+        let headers = match reader.headers() {
+            Ok(headers) => headers.clone(),
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to parse CSV header: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let start_row = params.start_row.unwrap_or(0);
+        let max_rows = params.max_rows.unwrap_or(100);
+        let mut rows = Vec::new();
+        let mut total_rows = 0;
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to parse CSV row {}: {}", total_rows, e),
+                        None,
+                    ));
+                }
+            };
+            if total_rows >= start_row && rows.len() < max_rows {
+                let row: serde_json::Map<String, serde_json::Value> = headers
+                    .iter()
+                    .zip(record.iter())
+                    .map(|(key, value)| (key.to_string(), value.into()))
+                    .collect();
+                rows.push(serde_json::Value::Object(row));
+            }
+            total_rows += 1;
+        }
+
+        let result = serde_json::json!({
+            "headers": headers.iter().collect::<Vec<_>>(),
+            "rows": rows,
+            "startRow": start_row,
+            "totalRows": total_rows,
+            "hasMore": start_row + rows.len() < total_rows,
+        });
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Append JSON objects as rows to a CSV file, writing the header when the file is new and checking the keys against the existing header otherwise",
+        annotations(read_only_hint = false, destructive_hint = false)
+    )]
+    async fn append_csv(
+        &self,
+        Parameters(params): Parameters<AppendCsvParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let delimiter = parse_delimiter(params.delimiter.as_deref())?;
+        let rows: Vec<serde_json::Map<String, serde_json::Value>> = match params.rows {
+            serde_json::Value::Object(row) => vec![row],
+            serde_json::Value::Array(rows) => {
+                match rows
+                    .into_iter()
+                    .map(|row| match row {
+                        serde_json::Value::Object(row) => Some(row),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                {
+                    Some(rows) => rows,
+                    None => {
+                        return Err(McpError::new(
+                            rmcp::model::ErrorCode::INVALID_PARAMS,
+                            "Every row must be a JSON object".to_string(),
+                            None,
+                        ));
+                    }
+                }
+            }
+            _ => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    "rows must be a JSON object or an array of objects".to_string(),
+                    None,
+                ));
+            }
+        };
+        let Some(first) = rows.first() else {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "No rows to append".to_string(),
+                None,
+            ));
+        };
+
+        let existing = match fs::read(&params.path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+        let is_new = existing.iter().all(u8::is_ascii_whitespace);
+        let headers: Vec<String> = if is_new {
+            first.keys().cloned().collect()
+        } else {
+            let mut reader = csv::ReaderBuilder::new()
+                .delimiter(delimiter)
+                .from_reader(existing.as_slice());
+            match reader.headers() {
+                Ok(headers) => headers.iter().map(str::to_string).collect(),
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to parse CSV header: {}", e),
+                        None,
+                    ));
+                }
+            }
+        };
+
+        for (index, row) in rows.iter().enumerate() {
+            let missing: Vec<&str> = headers
+                .iter()
+                .filter(|header| !row.contains_key(header.as_str()))
+                .map(String::as_str)
+                .collect();
+            let unknown: Vec<&str> = row
+                .keys()
+                .filter(|key| !headers.contains(key))
+                .map(String::as_str)
+                .collect();
+            if !missing.is_empty() || !unknown.is_empty() {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "Row {} does not match the header {:?}: missing {:?}, unknown {:?}",
+                        index, headers, missing, unknown
+                    ),
+                    None,
+                ));
+            }
+        }
+
+        let render = || -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+            let mut writer = csv::WriterBuilder::new()
+                .delimiter(delimiter)
+                .from_writer(Vec::new());
+            if is_new {
+                writer.write_record(&headers)?;
+            }
+            for row in &rows {
+                writer.write_record(headers.iter().map(|header| csv_field(&row[header])))?;
+            }
+            Ok(writer.into_inner()?)
+        };
+        let mut content = match render() {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to write CSV: {}", e),
+                    None,
+                ));
+            }
+        };
+        // Do not run the first new row into a last line without a line ending
+        if !is_new && !existing.ends_with(b"\n") {
+            content.insert(0, b'\n');
+        }
+        self.ensure_free_space(&params.path, content.len() as u64)?;
+
+        let append = async {
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&params.path)
+                .await?;
+            if is_new {
+                file.set_len(0).await?;
+            }
+            file.write_all(&content).await?;
+            file.flush().await
+        };
+        match append.await {
+            Ok(_) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Appended {} rows to {}",
+                rows.len(),
+                params.path
+            ))])),
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to write file: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Write a JSON value to a file as pretty-printed JSON, optionally merging it into the existing content",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn write_json(
+        &self,
+        Parameters(params): Parameters<WriteJsonParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut value = params.value;
+        if params.merge.unwrap_or(false) {
+            match fs::read_to_string(&params.path).await {
+                Ok(content) => match serde_json::from_str::<serde_json::Value>(&content) {
+                    Ok(mut existing) => {
+                        merge_json(&mut existing, value);
+                        value = existing;
+                    }
+                    Err(e) => {
+                        return Err(McpError::new(
+                            rmcp::model::ErrorCode::INVALID_PARAMS,
+                            format!("Existing file is not valid JSON: {}", e),
+                            None,
+                        ));
+                    }
+                },
+                // Nothing to merge into yet
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to read file: {}", e),
+                        None,
+                    ));
+                }
+            }
+        }
+
+        let content = match serde_json::to_string_pretty(&value) {
+            Ok(content) => content + "\n",
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Failed to serialize JSON: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        self.ensure_free_space(&params.path, content.len() as u64)?;
+        match fs::write(&params.path, &content).await {
+            Ok(_) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Successfully wrote JSON to {}",
+                params.path
+            ))])),
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to write file: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Parse a .env file into a JSON object of key/value pairs, optionally redacting secrets",
+        annotations(read_only_hint = true)
+    )]
+    async fn read_dotenv(
+        &self,
+        Parameters(params): Parameters<ReadDotenvParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let secret_pattern = match regex::Regex::new(
+            params
+                .secret_pattern
+                .as_deref()
+                .unwrap_or(DEFAULT_SECRET_PATTERN),
+        ) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid secret pattern: {}", e),
+                    None,
+                ));
+            }
+        };
+        let content = match fs::read_to_string(&params.path).await {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let redact = params.redact_secrets.unwrap_or(false);
+        let mut values = serde_json::Map::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let Some((key, raw_value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim();
+            let value = if redact && secret_pattern.is_match(key) {
+                "***".to_string()
+            } else {
+                parse_dotenv_value(raw_value)
+            };
+            values.insert(key.to_string(), value.into());
+        }
+
+        let result = serde_json::Value::Object(values);
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Convert config content between JSON, YAML and TOML",
+        annotations(read_only_hint = true)
+    )]
+    async fn convert_config(
+        &self,
+        Parameters(params): Parameters<ConvertConfigParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (content, from) = match (&params.path, params.content) {
+            (Some(path), None) => match fs::read_to_string(path).await {
+                Ok(content) => (
+                    content,
+                    params.from.or_else(|| ConfigFormat::from_path(path)),
+                ),
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to read file: {}", e),
+                        None,
+                    ));
+                }
+            },
+            (None, Some(content)) => (content, params.from),
+            _ => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    "Exactly one of path and content must be provided".to_string(),
+                    None,
+                ));
+            }
+        };
+        let Some(from) = from else {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "Cannot infer the input format, set from".to_string(),
+                None,
+            ));
+        };
+
+        let value = match parse_config(&content, from) {
+            Ok(value) => value,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Failed to parse input: {}", e),
+                    None,
+                ));
+            }
+        };
+        match render_config(&value, params.to) {
+            Ok(converted) => Ok(CallToolResult::success(vec![Content::text(converted)])),
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!("Failed to convert: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Encode inline text or the bytes of a file as base64",
+        annotations(read_only_hint = true)
+    )]
+    async fn encode_base64(
+        &self,
+        Parameters(params): Parameters<EncodeBase64Params>,
+    ) -> Result<CallToolResult, McpError> {
+        let bytes = match (params.content, &params.path) {
+            (Some(content), None) => content.into_bytes(),
+            (None, Some(path)) => match fs::read(path).await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to read file: {}", e),
+                        None,
+                    ));
+                }
+            },
+            _ => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    "Exactly one of content and path must be provided".to_string(),
+                    None,
+                ));
+            }
+        };
+
+        Ok(CallToolResult::success(vec![Content::text(
+            base64_engine(params.url_safe).encode(bytes),
+        )]))
+    }
+
+    #[tool(
+        description = "Decode base64 data, returning it as text or writing the bytes to a file",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn decode_base64(
+        &self,
+        Parameters(params): Parameters<DecodeBase64Params>,
+    ) -> Result<CallToolResult, McpError> {
+        let data: String = params.data.chars().filter(|c| !c.is_whitespace()).collect();
+        let bytes = match base64_engine(params.url_safe).decode(&data) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid base64: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        if let Some(output_path) = &params.output_path {
+            self.ensure_free_space(output_path, bytes.len() as u64)?;
+        }
+        match params.output_path {
+            Some(output_path) => match fs::write(&output_path, &bytes).await {
+                Ok(_) => Ok(CallToolResult::success(vec![Content::text(format!(
+                    "Decoded {} bytes to {}",
+                    bytes.len(),
+                    output_path
+                ))])),
+                Err(e) => Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to write file: {}", e),
+                    None,
+                )),
+            },
+            None => match String::from_utf8(bytes) {
+                Ok(text) => Ok(CallToolResult::success(vec![Content::text(text)])),
+                Err(_) => Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    "Decoded data is not valid UTF-8, set output_path to write it to a file"
+                        .to_string(),
+                    None,
+                )),
+            },
+        }
+    }
+
+    #[tool(
+        description = "Stream a newline-delimited JSON file and return only the records matching a field/value predicate, with paging",
+        annotations(read_only_hint = true)
+    )]
+    async fn filter_ndjson(
+        &self,
+        Parameters(params): Parameters<FilterNdjsonParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let file = match fs::File::open(&params.path).await {
+            Ok(file) => file,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to open file: {}", e),
+                    None,
+                ));
+            }
+        };
+        let offset = params.offset.unwrap_or(0);
+        let limit = params.limit.unwrap_or(100);
+
+        let mut lines = BufReader::new(file).lines();
+        let mut records = Vec::new();
+        let mut matched = 0;
+        let mut invalid_lines = 0;
+        let mut line_number = 0;
+        let mut has_more = false;
+        loop {
+            let line = match lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to read line {}: {}", line_number + 1, e),
+                        None,
+                    ));
+                }
+            };
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: serde_json::Value = match serde_json::from_str(&line) {
+                Ok(record) => record,
+                Err(_) => {
+                    invalid_lines += 1;
+                    continue;
+                }
+            };
+            let is_match = match &params.field {
+                None => true,
+                Some(field) => match (select_json_path(&record, field), &params.equals) {
+                    (Some(value), Some(expected)) => value == expected,
+                    (Some(value), None) => !value.is_null(),
+                    (None, _) => false,
+                },
+            };
+            if !is_match {
+                continue;
+            }
+            matched += 1;
+            if matched <= offset {
+                continue;
+            }
+            // One match past the page is enough to know there are more, so stop reading
+            if records.len() == limit {
+                has_more = true;
+                break;
+            }
+            records.push(serde_json::json!({ "line": line_number, "record": record }));
+        }
+
+        let result = serde_json::json!({
+            "path": params.path,
+            "records": records,
+            "offset": offset,
+            "hasMore": has_more,
+            "linesScanned": line_number,
+            "invalidLines": invalid_lines,
+        });
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Reformat a JSON, YAML or TOML file in place with consistent indentation, reporting whether it changed; files that fail to parse are left untouched",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn format_file(
+        &self,
+        Parameters(params): Parameters<FormatFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let Some(format) = params.format.or(ConfigFormat::from_path(&params.path)) else {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "Cannot tell the format from the path, set format".to_string(),
+                None,
+            ));
+        };
+        let content = match fs::read_to_string(&params.path).await {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+        // Rendering goes through a plain value, which would silently drop the comments
+        if !matches!(format, ConfigFormat::Json) && has_hash_comment(&content) {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!(
+                    "{} has comments, which formatting would remove",
+                    params.path
+                ),
+                None,
+            ));
+        }
+        let unparsable = |e: String| {
+            McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!(
+                    "{} does not parse, leaving it unchanged: {}",
+                    params.path, e
+                ),
+                None,
+            )
+        };
+
+        let formatted = match format {
+            ConfigFormat::Json => {
+                let value = parse_config(&content, format).map_err(unparsable)?;
+                let indent = " ".repeat(params.indent.unwrap_or(2));
+                let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+                let mut buffer = Vec::new();
+                let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+                serde::Serialize::serialize(&value, &mut serializer)
+                    .map_err(|e| e.to_string())
+                    .map(|_| String::from_utf8_lossy(&buffer).into_owned() + "\n")
+            }
+            ConfigFormat::Yaml => {
+                let value = parse_config(&content, format).map_err(unparsable)?;
+                render_config(&value, format)
+            }
+            // Kept in TOML's own value type: going through JSON would turn dates and times
+            // into strings
+            ConfigFormat::Toml => {
+                let value = toml::from_str::<toml::Value>(&content)
+                    .map_err(|e| unparsable(e.to_string()))?;
+                toml::to_string_pretty(&value).map_err(|e| e.to_string())
+            }
+        };
+        let formatted = match formatted {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to format {}: {}", params.path, e),
+                    None,
+                ));
+            }
+        };
+
+        let changed = formatted != content;
+        if changed && !params.check.unwrap_or(false) {
+            self.ensure_free_space(&params.path, formatted.len() as u64)?;
+            if let Err(e) = replace_file_atomically(&params.path, formatted.as_bytes()).await {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to write file: {}", e),
+                    None,
+                ));
+            }
+        }
+        let message = match (changed, params.check.unwrap_or(false)) {
+            (false, _) => format!("{} is already formatted", params.path),
+            (true, true) => format!("{} would change", params.path),
+            (true, false) => format!("Formatted {}", params.path),
+        };
+        Ok(CallToolResult::success(vec![Content::text(message)]))
+    }
+
+    #[tool(
+        description = "Apply a JSON Merge Patch (RFC 7386) or JSON Patch (RFC 6902) to a JSON file, writing it atomically, and return the resulting document; a file holding a JSON object is refused a patch that would turn it into an array or scalar",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn patch_json(
+        &self,
+        Parameters(params): Parameters<PatchJsonParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let content = match fs::read_to_string(&params.path).await {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+        let mut document = match serde_json::from_str::<serde_json::Value>(&content) {
+            Ok(document) => document,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("{} is not valid JSON: {}", params.path, e),
+                    None,
+                ));
+            }
+        };
+
+        let was_object = document.is_object();
+        let format = params.format.unwrap_or(if params.patch.is_array() {
+            PatchFormat::JsonPatch
+        } else {
+            PatchFormat::MergePatch
+        });
+        match format {
+            PatchFormat::MergePatch => json_patch::merge(&mut document, &params.patch),
+            PatchFormat::JsonPatch => {
+                let patch = match serde_json::from_value::<json_patch::Patch>(params.patch) {
+                    Ok(patch) => patch,
+                    Err(e) => {
+                        return Err(McpError::new(
+                            rmcp::model::ErrorCode::INVALID_PARAMS,
+                            format!("Invalid JSON Patch: {}", e),
+                            None,
+                        ));
+                    }
+                };
+                // Applied all or nothing, so a failed operation leaves the file as it was
+                if let Err(e) = json_patch::patch(&mut document, &patch) {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INVALID_PARAMS,
+                        format!("Failed to apply patch: {}", e),
+                        None,
+                    ));
+                }
+            }
+        }
+
+        // A config file is an object; a patch that replaces the whole document is a mistake
+        if was_object && !document.is_object() {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!(
+                    "The patch replaces the top-level object of {}, leaving it unchanged",
+                    params.path
+                ),
+                None,
+            ));
+        }
+        let patched = match serde_json::to_string_pretty(&document) {
+            Ok(patched) => patched + "\n",
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to serialize JSON: {}", e),
+                    None,
+                ));
+            }
+        };
+        self.ensure_free_space(&params.path, patched.len() as u64)?;
+        if let Err(e) = replace_file_atomically(&params.path, patched.as_bytes()).await {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to write file: {}", e),
+                None,
+            ));
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(patched)]))
+    }
+}
@@ -0,0 +1,1380 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use base64::{Engine as _, engine::general_purpose};
+use rmcp::{
+    ErrorData as McpError, RoleServer,
+    handler::server::wrapper::{Json, Parameters},
+    model::{CallToolResult, Content},
+    service::RequestContext,
+    tool, tool_router,
+};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::service::{
+    DiveDefaultService,
+    fetch::cancelled_result,
+    fs::{format_system_time, is_binary_file},
+    hash::{HashAlgorithm, hash_file, hash_file_with, to_hex},
+};
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct DirectorySummaryParams {
+    /// The path to the directory to summarize
+    path: String,
+    /// How many levels below the directory to descend (default unlimited)
+    #[serde(default)]
+    max_depth: Option<usize>,
+    /// How many of the most common file extensions to report (default 10)
+    #[serde(default)]
+    top_extensions: Option<usize>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct DirectorySummaryOutput {
+    /// The summarized directory
+    path: String,
+    /// Number of files, not counting symlinks
+    files: u64,
+    /// Number of subdirectories
+    directories: u64,
+    /// Total size of the files in bytes
+    total_size: u64,
+    /// The most common file extensions, most files first
+    extensions: Vec<ExtensionSummary>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct ExtensionSummary {
+    /// Lowercased extension, or "(none)"
+    extension: String,
+    /// Number of files with the extension
+    files: u64,
+    /// Total size of those files in bytes
+    size: u64,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct DiffDirectoriesParams {
+    /// The path to the first directory
+    left: String,
+    /// The path to the second directory
+    right: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct TreeParams {
+    /// The path to the directory to render
+    path: String,
+    /// How many levels below the directory to show, at least 1 (default 3)
+    #[serde(default)]
+    max_depth: Option<usize>,
+    /// Only show directories (default false)
+    #[serde(default)]
+    dirs_only: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct WatchDirectoryParams {
+    /// The path to the directory to watch
+    path: String,
+    /// Glob the new file's name must match, e.g. "*.csv" (default any file)
+    #[serde(default)]
+    pattern: Option<String>,
+    /// How long to wait for a new file in milliseconds (default 30000)
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// How often to list the directory in milliseconds (default 500)
+    #[serde(default)]
+    poll_interval_ms: Option<u64>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct BatchRenameParams {
+    /// The directory whose files to rename, including those in subdirectories
+    root: String,
+    /// Glob the file names must match, e.g. "*.txt" (default every file)
+    #[serde(default)]
+    pattern: Option<String>,
+    /// Regex matched against each file name; files it does not match are left alone
+    find: String,
+    /// Replacement for the first match, referring to capture groups as $1 or ${name}
+    replace: String,
+    /// Only report the renames without performing them (default false)
+    #[serde(default)]
+    dry_run: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ReadMatchingParams {
+    /// The directory to search, including its subdirectories
+    root: String,
+    /// Glob each file's path relative to the root must match, e.g. "src/**/*.rs"
+    glob: String,
+    /// How many levels below the root to descend (default unlimited)
+    #[serde(default)]
+    max_depth: Option<usize>,
+    /// Maximum number of files to read in this page (default 20)
+    #[serde(default)]
+    max_files: Option<usize>,
+    /// Maximum number of bytes to read across the files of this page (default 1048576)
+    #[serde(default)]
+    max_total_bytes: Option<u64>,
+    /// The next_cursor of the previous page, to continue after the files it read
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct SkippedFile {
+    /// Path of the file relative to the root
+    path: String,
+    /// Why the file was not read
+    reason: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReadMatchingOutput {
+    /// The searched directory
+    root: String,
+    /// Content of each file read, keyed by its path relative to the root
+    files: BTreeMap<String, String>,
+    /// Matching files that were not read
+    skipped: Vec<SkippedFile>,
+    /// Whether more matching files follow this page
+    has_more: bool,
+    /// Cursor to pass to get the next page, when there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ChecksumDirectoryParams {
+    /// The directory whose files to hash, including those in subdirectories
+    root: String,
+    /// Glob each file's path relative to the root must match, e.g. "*.rs" or "src/**" (default
+    /// every file)
+    #[serde(default)]
+    pattern: Option<String>,
+    /// How many levels below the root to descend (default unlimited)
+    #[serde(default)]
+    max_depth: Option<usize>,
+    /// Digest algorithm: sha256 (default), sha384 or sha512
+    #[serde(default)]
+    algorithm: HashAlgorithm,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct ChecksumManifest {
+    /// The hashed directory
+    root: String,
+    /// The digest algorithm used
+    algorithm: String,
+    /// Hex digest of every file, keyed by its path relative to the root
+    files: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct DirectoryHashParams {
+    /// The directory whose whole content to hash
+    root: String,
+    /// Globs of relative paths to leave out, such as ".git" or "target"; a matching
+    /// directory excludes everything below it
+    #[serde(default)]
+    exclude: Option<Vec<String>>,
+    /// Digest algorithm: sha256 (default), sha384 or sha512
+    #[serde(default)]
+    algorithm: HashAlgorithm,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct DirectoryHash {
+    /// The hashed directory
+    root: String,
+    /// The digest algorithm used
+    algorithm: String,
+    /// Hex digest of the relative paths and contents of all included files; it changes when
+    /// any file is added, removed, renamed or edited
+    hash: String,
+    /// Number of files that contributed to the hash
+    file_count: usize,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct VerifyManifestParams {
+    /// The directory to check against the manifest
+    root: String,
+    /// Hex digests keyed by path relative to the root, such as the files of
+    /// checksum_directory
+    manifest: BTreeMap<String, String>,
+    /// Glob limiting which files count as added, as given to checksum_directory (default
+    /// every file)
+    #[serde(default)]
+    pattern: Option<String>,
+    /// How many levels below the root to look for added files (default unlimited)
+    #[serde(default)]
+    max_depth: Option<usize>,
+    /// Digest algorithm the manifest was computed with (default sha256)
+    #[serde(default)]
+    algorithm: HashAlgorithm,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ManifestDrift {
+    /// The checked directory
+    root: String,
+    /// Files whose digest no longer matches the manifest
+    changed: Vec<String>,
+    /// Files in the manifest that no longer exist
+    missing: Vec<String>,
+    /// Files that exist but are not in the manifest
+    added: Vec<String>,
+    /// Number of files matching the manifest
+    unchanged: usize,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct CreateTreeParams {
+    /// The directory to create the tree under, created if missing
+    root: String,
+    /// The tree to create: each key is a file or directory name, a nested object is a
+    /// directory, a string is a file's content and null an empty file, e.g.
+    /// {"src": {"main.rs": "fn main() {}"}, "README.md": null}
+    spec: serde_json::Map<String, serde_json::Value>,
+    /// Replace files that already exist (default false)
+    #[serde(default)]
+    overwrite: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct RecentFilesParams {
+    /// The directory to search, including its subdirectories
+    path: String,
+    /// Maximum number of files to return (default 20)
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Only return files modified after this RFC3339 timestamp
+    #[serde(default)]
+    since: Option<String>,
+    /// How many levels below the directory to descend (default unlimited)
+    #[serde(default)]
+    max_depth: Option<usize>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct RecentFilesOutput {
+    /// The searched directory
+    path: String,
+    /// The most recently modified files, newest first
+    files: Vec<RecentFile>,
+    /// Number of files that passed the since filter, including those beyond the limit
+    total_matching: usize,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct RecentFile {
+    path: String,
+    /// Last modification time as RFC3339
+    modified: String,
+    /// Size in bytes
+    size: u64,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct FindDuplicatesParams {
+    /// The directory to search, including its subdirectories
+    root: String,
+    /// Ignore files smaller than this many bytes (default 1, skipping empty files)
+    #[serde(default)]
+    min_size: Option<u64>,
+    /// How many levels below the root to descend (default unlimited)
+    #[serde(default)]
+    max_depth: Option<usize>,
+    /// Maximum number of groups to return in this page (default 100)
+    #[serde(default)]
+    limit: Option<usize>,
+    /// The next_cursor of the previous page, to continue after the groups it returned
+    #[serde(default)]
+    cursor: Option<String>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct DuplicateGroup {
+    /// SHA-256 digest shared by the files
+    hash: String,
+    /// Size of each file in bytes
+    size: u64,
+    /// Bytes taken by all copies but one
+    wasted: u64,
+    /// The identical files, sorted
+    paths: Vec<String>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct FindDuplicatesOutput {
+    /// The searched directory
+    root: String,
+    /// Groups of identical files in this page, most wasted space first
+    groups: Vec<DuplicateGroup>,
+    /// Bytes taken by all duplicate copies together, across every page
+    total_wasted: u64,
+    /// Whether more groups follow this page
+    has_more: bool,
+    /// Cursor to pass to get the next page, when there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+/// A file or directory found while walking a directory tree
+pub struct WalkEntry {
+    pub path: PathBuf,
+    pub metadata: std::fs::Metadata,
+}
+
+/// Recursively collect the entries under a root, skipping symlinks and descending at most
+/// `max_depth` levels below it, so 0 collects the root's own entries only. Unreadable
+/// subdirectories are skipped.
+pub async fn walk_directory(
+    root: &Path,
+    max_depth: Option<usize>,
+) -> Result<Vec<WalkEntry>, std::io::Error> {
+    let mut entries = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), 0)];
+    let mut root_read = false;
+
+    while let Some((dir, depth)) = pending.pop() {
+        let mut read_dir = match fs::read_dir(&dir).await {
+            Ok(read_dir) => read_dir,
+            Err(e) if !root_read => return Err(e),
+            Err(_) => continue,
+        };
+        root_read = true;
+
+        while let Ok(Some(entry)) = read_dir.next_entry().await {
+            let Ok(metadata) = fs::symlink_metadata(entry.path()).await else {
+                continue;
+            };
+            if metadata.is_symlink() {
+                continue;
+            }
+            if metadata.is_dir() && max_depth.is_none_or(|max| depth < max) {
+                pending.push((entry.path(), depth + 1));
+            }
+            entries.push(WalkEntry {
+                path: entry.path(),
+                metadata,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Collect the files under a root keyed by their path relative to it
+async fn files_by_relative_path(
+    root: &Path,
+) -> Result<BTreeMap<String, WalkEntry>, std::io::Error> {
+    let entries = walk_directory(root, None).await?;
+    Ok(entries
+        .into_iter()
+        .filter(|entry| !entry.metadata.is_dir())
+        .filter_map(|entry| {
+            let relative = entry
+                .path
+                .strip_prefix(root)
+                .ok()?
+                .to_string_lossy()
+                .into_owned();
+            Some((relative, entry))
+        })
+        .collect())
+}
+
+/// Collect the files under a root whose path relative to it matches a glob, keyed by that
+/// relative path
+async fn matching_files(
+    root: &Path,
+    pattern: Option<&str>,
+    max_depth: Option<usize>,
+) -> Result<BTreeMap<String, PathBuf>, McpError> {
+    let pattern = match pattern.map(glob::Pattern::new).transpose() {
+        Ok(pattern) => pattern,
+        Err(e) => {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!("Invalid pattern: {}", e),
+                None,
+            ));
+        }
+    };
+    let entries = match walk_directory(root, max_depth).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to read directory: {}", e),
+                None,
+            ));
+        }
+    };
+    Ok(entries
+        .into_iter()
+        .filter(|entry| !entry.metadata.is_dir())
+        .filter_map(|entry| {
+            let relative = entry.path.strip_prefix(root).ok()?.to_path_buf();
+            if let Some(pattern) = &pattern
+                && !pattern.matches_path(&relative)
+            {
+                return None;
+            }
+            Some((relative.to_string_lossy().into_owned(), entry.path))
+        })
+        .collect())
+}
+
+/// Encode a position in a sorted traversal as an opaque page cursor
+fn encode_cursor(position: &str) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(position)
+}
+
+/// Decode a page cursor made by `encode_cursor` back into its position
+fn decode_cursor(cursor: &str) -> Result<String, McpError> {
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|position| String::from_utf8(position).ok())
+        .ok_or_else(|| {
+            McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!("Invalid cursor: {}", cursor),
+                None,
+            )
+        })
+}
+
+/// Combine the digests of files into one, feeding each relative path with `/` separators
+/// and its digest in path order so the result is the same on every platform
+fn combine_digests<D: sha2::Digest>(digests: &BTreeMap<String, String>) -> String {
+    let mut hasher = D::new();
+    for (relative, digest) in digests {
+        hasher.update(relative.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(digest.as_bytes());
+        hasher.update(b"\n");
+    }
+    to_hex(&hasher.finalize())
+}
+
+/// Flatten a `create_tree` spec into relative paths and file contents, `None` for directories.
+/// Names must stay below the root: absolute paths, `..` and `.` are refused.
+fn flatten_tree_spec(
+    spec: &serde_json::Map<String, serde_json::Value>,
+    parent: &Path,
+    entries: &mut Vec<(PathBuf, Option<String>)>,
+) -> Result<(), McpError> {
+    for (name, value) in spec {
+        let relative = Path::new(name);
+        if name.is_empty()
+            || !relative
+                .components()
+                .all(|component| matches!(component, std::path::Component::Normal(_)))
+        {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Invalid name {:?} in spec: names must stay below the root",
+                    name
+                ),
+                None,
+            ));
+        }
+        let path = parent.join(relative);
+        match value {
+            serde_json::Value::Object(children) => {
+                entries.push((path.clone(), None));
+                flatten_tree_spec(children, &path, entries)?;
+            }
+            serde_json::Value::String(content) => entries.push((path, Some(content.clone()))),
+            serde_json::Value::Null => entries.push((path, Some(String::new()))),
+            _ => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "Invalid value of {} in spec: expected an object, a string or null",
+                        path.display()
+                    ),
+                    None,
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The names of the files directly inside a directory
+async fn list_file_names(dir: &Path) -> Result<HashSet<String>, std::io::Error> {
+    let mut names = HashSet::new();
+    let mut read_dir = fs::read_dir(dir).await?;
+    while let Some(entry) = read_dir.next_entry().await? {
+        if entry
+            .file_type()
+            .await
+            .is_ok_and(|file_type| !file_type.is_dir())
+        {
+            names.insert(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    Ok(names)
+}
+
+/// Append the children of `dir` to an ASCII tree, recursing into subdirectories
+fn render_tree(
+    dir: &Path,
+    children: &HashMap<PathBuf, Vec<(String, bool)>>,
+    prefix: &str,
+    output: &mut String,
+) {
+    let Some(entries) = children.get(dir) else {
+        return;
+    };
+    for (index, (name, is_dir)) in entries.iter().enumerate() {
+        let last = index + 1 == entries.len();
+        let (branch, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        output.push_str(prefix);
+        output.push_str(branch);
+        output.push_str(name);
+        if *is_dir {
+            output.push('/');
+        }
+        output.push('\n');
+        if *is_dir {
+            render_tree(
+                &dir.join(name),
+                children,
+                &format!("{}{}", prefix, indent),
+                output,
+            );
+        }
+    }
+}
+
+#[tool_router(router = tool_router_directory, vis = "pub")]
+impl DiveDefaultService {
+    #[tool(
+        description = "Summarize a directory tree: file and directory counts, total size and the most common file extensions",
+        annotations(read_only_hint = true)
+    )]
+    async fn directory_summary(
+        &self,
+        Parameters(params): Parameters<DirectorySummaryParams>,
+    ) -> Result<Json<DirectorySummaryOutput>, McpError> {
+        let entries = match walk_directory(Path::new(&params.path), params.max_depth).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read directory: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let mut files = 0;
+        let mut directories = 0;
+        let mut total_size = 0;
+        // extension -> (file count, total size)
+        let mut extensions: HashMap<String, (u64, u64)> = HashMap::new();
+        for entry in &entries {
+            if entry.metadata.is_dir() {
+                directories += 1;
+                continue;
+            }
+            files += 1;
+            total_size += entry.metadata.len();
+
+            let extension = entry
+                .path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_else(|| "(none)".to_string());
+            let stats = extensions.entry(extension).or_default();
+            stats.0 += 1;
+            stats.1 += entry.metadata.len();
+        }
+
+        let mut extensions: Vec<(String, (u64, u64))> = extensions.into_iter().collect();
+        extensions.sort_by(|a, b| b.1.0.cmp(&a.1.0).then_with(|| a.0.cmp(&b.0)));
+        extensions.truncate(params.top_extensions.unwrap_or(10));
+
+        Ok(Json(DirectorySummaryOutput {
+            path: params.path,
+            files,
+            directories,
+            total_size,
+            extensions: extensions
+                .into_iter()
+                .map(|(extension, (files, size))| ExtensionSummary {
+                    extension,
+                    files,
+                    size,
+                })
+                .collect(),
+        }))
+    }
+
+    #[tool(
+        description = "Compare two directory trees and list the files only in one of them and the files whose content differs",
+        annotations(read_only_hint = true)
+    )]
+    async fn diff_directories(
+        &self,
+        Parameters(params): Parameters<DiffDirectoriesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (left_root, right_root) = (Path::new(&params.left), Path::new(&params.right));
+        let (left, right) = match tokio::try_join!(
+            files_by_relative_path(left_root),
+            files_by_relative_path(right_root)
+        ) {
+            Ok(files) => files,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read directory: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let only_in_left: Vec<&String> = left
+            .keys()
+            .filter(|path| !right.contains_key(*path))
+            .collect();
+        let only_in_right: Vec<&String> = right
+            .keys()
+            .filter(|path| !left.contains_key(*path))
+            .collect();
+
+        let mut differ = Vec::new();
+        let mut identical = 0;
+        for (path, left_entry) in &left {
+            let Some(right_entry) = right.get(path) else {
+                continue;
+            };
+            // Only files of the same size need their content compared
+            let same = left_entry.metadata.len() == right_entry.metadata.len()
+                && match tokio::try_join!(hash_file(&left_entry.path), hash_file(&right_entry.path))
+                {
+                    Ok((left_hash, right_hash)) => left_hash == right_hash,
+                    Err(e) => {
+                        return Err(McpError::new(
+                            rmcp::model::ErrorCode::INTERNAL_ERROR,
+                            format!("Failed to hash {}: {}", path, e),
+                            None,
+                        ));
+                    }
+                };
+            if same {
+                identical += 1;
+            } else {
+                differ.push(path);
+            }
+        }
+
+        let result = serde_json::json!({
+            "left": params.left,
+            "right": params.right,
+            "onlyInLeft": only_in_left,
+            "onlyInRight": only_in_right,
+            "differ": differ,
+            "identical": identical,
+        });
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Render a directory structure as an ASCII tree",
+        annotations(read_only_hint = true)
+    )]
+    async fn tree(
+        &self,
+        Parameters(params): Parameters<TreeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let root = Path::new(&params.path);
+        let levels = params.max_depth.unwrap_or(3);
+        if levels == 0 {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "max_depth must be at least 1".to_string(),
+                None,
+            ));
+        }
+        // The first level shown is the directory's own entries, which are not a descent
+        let entries = match walk_directory(root, Some(levels - 1)).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read directory: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let dirs_only = params.dirs_only.unwrap_or(false);
+        let mut directories = 0;
+        let mut files = 0;
+        let mut children: HashMap<PathBuf, Vec<(String, bool)>> = HashMap::new();
+        for entry in entries {
+            let is_dir = entry.metadata.is_dir();
+            if is_dir {
+                directories += 1;
+            } else if dirs_only {
+                continue;
+            } else {
+                files += 1;
+            }
+            if let (Some(parent), Some(name)) = (entry.path.parent(), entry.path.file_name()) {
+                children
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push((name.to_string_lossy().into_owned(), is_dir));
+            }
+        }
+        for entries in children.values_mut() {
+            entries.sort();
+        }
+
+        let mut output = format!("{}\n", params.path);
+        render_tree(root, &children, "", &mut output);
+        if dirs_only {
+            output.push_str(&format!("\n{} directories", directories));
+        } else {
+            output.push_str(&format!("\n{} directories, {} files", directories, files));
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(output)]))
+    }
+
+    #[tool(
+        description = "Wait for a new file, optionally matching a glob, to appear in a directory and return its path",
+        annotations(read_only_hint = true)
+    )]
+    async fn watch_directory(
+        &self,
+        Parameters(params): Parameters<WatchDirectoryParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let pattern = match params
+            .pattern
+            .as_deref()
+            .map(glob::Pattern::new)
+            .transpose()
+        {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid pattern: {}", e),
+                    None,
+                ));
+            }
+        };
+        let dir = Path::new(&params.path);
+        let timeout = params.timeout_ms.unwrap_or(30_000);
+        let poll_interval = Duration::from_millis(params.poll_interval_ms.unwrap_or(500).max(10));
+
+        let mut seen = match list_file_names(dir).await {
+            Ok(names) => names,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read directory: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout);
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline.min(tokio::time::Instant::now() + poll_interval)) => {}
+                _ = context.ct.cancelled() => return Ok(cancelled_result("Watching the directory")),
+            }
+
+            // A directory that disappears mid-watch is treated as empty until it returns
+            let names = list_file_names(dir).await.unwrap_or_default();
+            let mut new_names: Vec<&String> = names
+                .difference(&seen)
+                .filter(|name| pattern.as_ref().is_none_or(|pattern| pattern.matches(name)))
+                .collect();
+            if !new_names.is_empty() {
+                new_names.sort();
+                return Ok(CallToolResult::success(vec![Content::text(
+                    dir.join(new_names[0]).to_string_lossy(),
+                )]));
+            }
+            seen = names;
+
+            if tokio::time::Instant::now() >= deadline {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "No new file appeared in {} within {} ms",
+                    params.path, timeout
+                ))]));
+            }
+        }
+    }
+
+    #[tool(
+        description = "Rename the files under a directory whose names match a glob, using a regex and replacement template, and list the renames",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn batch_rename(
+        &self,
+        Parameters(params): Parameters<BatchRenameParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let pattern = match params
+            .pattern
+            .as_deref()
+            .map(glob::Pattern::new)
+            .transpose()
+        {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid pattern: {}", e),
+                    None,
+                ));
+            }
+        };
+        let find = match regex::Regex::new(&params.find) {
+            Ok(find) => find,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid find regex: {}", e),
+                    None,
+                ));
+            }
+        };
+        let mut entries = match walk_directory(Path::new(&params.root), None).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read directory: {}", e),
+                    None,
+                ));
+            }
+        };
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let dry_run = params.dry_run.unwrap_or(false);
+
+        let mut renames = Vec::new();
+        let mut targets = HashSet::new();
+        for entry in entries.iter().filter(|entry| entry.metadata.is_file()) {
+            let Some(name) = entry.path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if !pattern.as_ref().is_none_or(|pattern| pattern.matches(name)) || !find.is_match(name)
+            {
+                continue;
+            }
+            let new_name = find.replace(name, params.replace.as_str());
+            if new_name == name {
+                continue;
+            }
+            let target = entry.path.with_file_name(new_name.as_ref());
+
+            // Never let a rename replace an existing file or another renamed one
+            let error = if new_name.is_empty() || new_name.contains(['/', '\\']) {
+                Some("the new name is not a plain file name".to_string())
+            } else if !targets.insert(target.clone()) {
+                Some("another file is renamed to the same name".to_string())
+            } else if fs::symlink_metadata(&target).await.is_ok() {
+                Some("the target already exists".to_string())
+            } else if dry_run {
+                None
+            } else {
+                fs::rename(&entry.path, &target)
+                    .await
+                    .err()
+                    .map(|e| e.to_string())
+            };
+
+            let mut rename = serde_json::json!({
+                "from": entry.path.to_string_lossy(),
+                "to": target.to_string_lossy(),
+            });
+            if let Some(error) = error {
+                rename["error"] = error.into();
+            }
+            renames.push(rename);
+        }
+
+        let result = serde_json::json!({
+            "dryRun": dry_run,
+            "renames": renames,
+        });
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Read every text file under a directory whose relative path matches a glob, a page of up to a file count and total size at a time, reporting the files skipped and why",
+        annotations(read_only_hint = true)
+    )]
+    async fn read_matching(
+        &self,
+        Parameters(params): Parameters<ReadMatchingParams>,
+    ) -> Result<Json<ReadMatchingOutput>, McpError> {
+        let matches = matching_files(
+            Path::new(&params.root),
+            Some(&params.glob),
+            params.max_depth,
+        )
+        .await?;
+        // Files are visited in path order, so a cursor is the last path of the previous page
+        let after = params.cursor.as_deref().map(decode_cursor).transpose()?;
+        let max_files = params.max_files.unwrap_or(20);
+        let max_total_bytes = params.max_total_bytes.unwrap_or(1024 * 1024);
+        let mut remaining_bytes = max_total_bytes;
+
+        let mut files = BTreeMap::new();
+        let mut skipped = Vec::new();
+        let mut last = None;
+        let mut has_more = false;
+        for (relative, path) in matches {
+            if after.as_ref().is_some_and(|after| relative <= *after) {
+                continue;
+            }
+            if files.len() >= max_files {
+                has_more = true;
+                break;
+            }
+            let reason = match fs::metadata(&path).await {
+                // Left for the next page, unless even a page of its own could not hold it
+                Ok(metadata) if metadata.len() > remaining_bytes && !files.is_empty() => {
+                    has_more = true;
+                    break;
+                }
+                Ok(metadata) if metadata.len() > max_total_bytes => Some(format!(
+                    "{} bytes would exceed max_total_bytes",
+                    metadata.len()
+                )),
+                Ok(_) => match is_binary_file(&path.to_string_lossy()).await {
+                    Ok(true) => Some("binary file".to_string()),
+                    Ok(false) => match fs::read_to_string(&path).await {
+                        Ok(content) => {
+                            remaining_bytes = remaining_bytes.saturating_sub(content.len() as u64);
+                            files.insert(relative.clone(), content);
+                            None
+                        }
+                        Err(e) => Some(format!("failed to read: {}", e)),
+                    },
+                    Err(e) => Some(format!("failed to read: {}", e)),
+                },
+                Err(e) => Some(format!("failed to read: {}", e)),
+            };
+            if let Some(reason) = reason {
+                skipped.push(SkippedFile {
+                    path: relative.clone(),
+                    reason,
+                });
+            }
+            last = Some(relative);
+        }
+
+        Ok(Json(ReadMatchingOutput {
+            root: params.root,
+            files,
+            skipped,
+            has_more,
+            next_cursor: last.filter(|_| has_more).map(|last| encode_cursor(&last)),
+        }))
+    }
+
+    #[tool(
+        description = "Hash every file under a directory, returning a manifest of relative path to digest",
+        annotations(read_only_hint = true)
+    )]
+    async fn checksum_directory(
+        &self,
+        Parameters(params): Parameters<ChecksumDirectoryParams>,
+    ) -> Result<Json<ChecksumManifest>, McpError> {
+        let files = matching_files(
+            Path::new(&params.root),
+            params.pattern.as_deref(),
+            params.max_depth,
+        )
+        .await?;
+
+        let mut manifest = BTreeMap::new();
+        for (relative, path) in files {
+            match hash_file_with(&path, params.algorithm).await {
+                Ok(digest) => {
+                    manifest.insert(relative, digest);
+                }
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to hash {}: {}", relative, e),
+                        None,
+                    ));
+                }
+            }
+        }
+
+        Ok(Json(ChecksumManifest {
+            root: params.root,
+            algorithm: params.algorithm.name().to_string(),
+            files: manifest,
+        }))
+    }
+
+    #[tool(
+        description = "Compute one stable hash of a directory tree from the relative paths and contents of its files, for cache keys and change detection",
+        annotations(read_only_hint = true)
+    )]
+    async fn directory_hash(
+        &self,
+        Parameters(params): Parameters<DirectoryHashParams>,
+    ) -> Result<Json<DirectoryHash>, McpError> {
+        let exclude = match params
+            .exclude
+            .iter()
+            .flatten()
+            .map(|pattern| glob::Pattern::new(pattern))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(exclude) => exclude,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid exclude pattern: {}", e),
+                    None,
+                ));
+            }
+        };
+        let files = matching_files(Path::new(&params.root), None, None).await?;
+
+        let mut digests = BTreeMap::new();
+        for (relative, path) in files {
+            let relative_path = Path::new(&relative);
+            if relative_path
+                .ancestors()
+                .filter(|ancestor| !ancestor.as_os_str().is_empty())
+                .any(|ancestor| exclude.iter().any(|pattern| pattern.matches_path(ancestor)))
+            {
+                continue;
+            }
+            match hash_file_with(&path, params.algorithm).await {
+                Ok(digest) => {
+                    let key = relative_path
+                        .components()
+                        .map(|component| component.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    digests.insert(key, digest);
+                }
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to hash {}: {}", relative, e),
+                        None,
+                    ));
+                }
+            }
+        }
+
+        let hash = match params.algorithm {
+            HashAlgorithm::Sha256 => combine_digests::<sha2::Sha256>(&digests),
+            HashAlgorithm::Sha384 => combine_digests::<sha2::Sha384>(&digests),
+            HashAlgorithm::Sha512 => combine_digests::<sha2::Sha512>(&digests),
+        };
+        Ok(Json(DirectoryHash {
+            root: params.root,
+            algorithm: params.algorithm.name().to_string(),
+            hash,
+            file_count: digests.len(),
+        }))
+    }
+
+    #[tool(
+        description = "Check a directory against a checksum manifest, listing the files that changed, went missing or were added",
+        annotations(read_only_hint = true)
+    )]
+    async fn verify_manifest(
+        &self,
+        Parameters(params): Parameters<VerifyManifestParams>,
+    ) -> Result<Json<ManifestDrift>, McpError> {
+        let root = Path::new(&params.root);
+        let files = matching_files(root, params.pattern.as_deref(), params.max_depth).await?;
+
+        let mut changed = Vec::new();
+        let mut missing = Vec::new();
+        let mut unchanged = 0;
+        for (relative, expected) in &params.manifest {
+            let path = files
+                .get(relative)
+                .cloned()
+                .unwrap_or_else(|| root.join(relative));
+            match hash_file_with(&path, params.algorithm).await {
+                Ok(digest) if digest.eq_ignore_ascii_case(expected.trim()) => unchanged += 1,
+                Ok(_) => changed.push(relative.clone()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    missing.push(relative.clone())
+                }
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to hash {}: {}", relative, e),
+                        None,
+                    ));
+                }
+            }
+        }
+        let added = files
+            .into_keys()
+            .filter(|relative| !params.manifest.contains_key(relative))
+            .collect();
+
+        Ok(Json(ManifestDrift {
+            root: params.root,
+            changed,
+            missing,
+            added,
+            unchanged,
+        }))
+    }
+
+    #[tool(
+        description = "Create a tree of directories and files under a root from a nested JSON spec in one call",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn create_tree(
+        &self,
+        Parameters(params): Parameters<CreateTreeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut entries = Vec::new();
+        flatten_tree_spec(&params.spec, Path::new(""), &mut entries)?;
+        let root = Path::new(&params.root);
+
+        // Check for conflicts up front so a refused spec creates nothing
+        let overwrite = params.overwrite.unwrap_or(false);
+        for (relative, content) in &entries {
+            let Ok(metadata) = fs::metadata(root.join(relative)).await else {
+                continue;
+            };
+            let conflict = match content {
+                None => !metadata.is_dir(),
+                Some(_) => metadata.is_dir() || !overwrite,
+            };
+            if conflict {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("{} already exists", relative.display()),
+                    None,
+                ));
+            }
+        }
+        let size = entries
+            .iter()
+            .filter_map(|(_, content)| content.as_ref())
+            .map(|content| content.len() as u64)
+            .sum();
+        self.ensure_free_space(&params.root, size)?;
+
+        let create = async {
+            fs::create_dir_all(root).await?;
+            let (mut directories, mut files) = (0, 0);
+            for (relative, content) in &entries {
+                let path = root.join(relative);
+                match content {
+                    None => {
+                        fs::create_dir_all(&path).await?;
+                        directories += 1;
+                    }
+                    Some(content) => {
+                        // A name such as "lib/mod.rs" also implies its directories
+                        if let Some(parent) = path.parent() {
+                            fs::create_dir_all(parent).await?;
+                        }
+                        fs::write(&path, content).await?;
+                        files += 1;
+                    }
+                }
+            }
+            Ok::<_, std::io::Error>((directories, files))
+        };
+        match create.await {
+            Ok((directories, files)) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Created {} directories and {} files under {}",
+                directories, files, params.root
+            ))])),
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to create tree: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "List the most recently modified files under a directory, newest first",
+        annotations(read_only_hint = true)
+    )]
+    async fn recent_files(
+        &self,
+        Parameters(params): Parameters<RecentFilesParams>,
+    ) -> Result<Json<RecentFilesOutput>, McpError> {
+        let since = match params
+            .since
+            .as_deref()
+            .map(chrono::DateTime::parse_from_rfc3339)
+            .transpose()
+        {
+            Ok(since) => since.map(std::time::SystemTime::from),
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid since timestamp: {}", e),
+                    None,
+                ));
+            }
+        };
+        let entries = match walk_directory(Path::new(&params.path), params.max_depth).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read directory: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let mut files: Vec<(std::time::SystemTime, WalkEntry)> = entries
+            .into_iter()
+            .filter(|entry| entry.metadata.is_file())
+            .filter_map(|entry| Some((entry.metadata.modified().ok()?, entry)))
+            .filter(|(modified, _)| since.is_none_or(|since| *modified > since))
+            .collect();
+        files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        let total_matching = files.len();
+        files.truncate(params.limit.unwrap_or(20));
+
+        Ok(Json(RecentFilesOutput {
+            path: params.path,
+            files: files
+                .into_iter()
+                .map(|(modified, entry)| RecentFile {
+                    path: entry.path.to_string_lossy().into_owned(),
+                    modified: format_system_time(modified),
+                    size: entry.metadata.len(),
+                })
+                .collect(),
+            total_matching,
+        }))
+    }
+
+    #[tool(
+        description = "Find groups of files with identical content under a directory, sorted by wasted space, a page at a time",
+        annotations(read_only_hint = true)
+    )]
+    async fn find_duplicates(
+        &self,
+        Parameters(params): Parameters<FindDuplicatesParams>,
+    ) -> Result<Json<FindDuplicatesOutput>, McpError> {
+        let entries = match walk_directory(Path::new(&params.root), params.max_depth).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read directory: {}", e),
+                    None,
+                ));
+            }
+        };
+        let min_size = params.min_size.unwrap_or(1);
+
+        // Only files sharing their size with another file can be duplicates, so hash just those
+        let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+        for entry in entries {
+            if entry.metadata.is_file() && entry.metadata.len() >= min_size {
+                by_size
+                    .entry(entry.metadata.len())
+                    .or_default()
+                    .push(entry.path);
+            }
+        }
+
+        let mut groups = Vec::new();
+        for (size, paths) in by_size.into_iter().filter(|(_, paths)| paths.len() > 1) {
+            let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+            for path in paths {
+                // Files that vanished or cannot be read are not reported as duplicates
+                if let Ok(hash) = hash_file(&path).await {
+                    by_hash
+                        .entry(hash)
+                        .or_default()
+                        .push(path.to_string_lossy().into_owned());
+                }
+            }
+            for (hash, mut paths) in by_hash.into_iter().filter(|(_, paths)| paths.len() > 1) {
+                paths.sort();
+                groups.push(DuplicateGroup {
+                    hash,
+                    size,
+                    wasted: size * (paths.len() as u64 - 1),
+                    paths,
+                });
+            }
+        }
+        groups.sort_by(|a, b| b.wasted.cmp(&a.wasted).then_with(|| a.paths.cmp(&b.paths)));
+
+        // The groups sort the same on every call, so a cursor is the count already returned
+        let start = match params.cursor.as_deref() {
+            Some(cursor) => decode_cursor(cursor)?.parse::<usize>().map_err(|_| {
+                McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid cursor: {}", cursor),
+                    None,
+                )
+            })?,
+            None => 0,
+        };
+        let limit = params.limit.unwrap_or(100).max(1);
+        let total_wasted = groups.iter().map(|group| group.wasted).sum();
+        let end = start.saturating_add(limit).min(groups.len());
+        let has_more = end < groups.len();
+        let page = groups.into_iter().skip(start).take(limit).collect();
+
+        Ok(Json(FindDuplicatesOutput {
+            root: params.root,
+            groups: page,
+            total_wasted,
+            has_more,
+            next_cursor: has_more.then(|| encode_cursor(&end.to_string())),
+        }))
+    }
+}
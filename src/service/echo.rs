@@ -16,7 +16,10 @@ struct EchoParam {
 
 #[tool_router(router = tool_router_echo, vis = "pub")]
 impl DiveDefaultService {
-    #[tool(description = "Repeat what you say")]
+    #[tool(
+        description = "Repeat what you say",
+        annotations(read_only_hint = true)
+    )]
     async fn echo(
         &self,
         Parameters(object): Parameters<EchoParam>,
@@ -1,16 +1,36 @@
 use crate::service::DiveDefaultService;
+use crate::service::data::select_json_path;
+use crate::service::fs::{detect_mime, is_binary_file};
+use crate::service::hash::{HashAlgorithm, hash_file_with, to_hex};
+use crate::service::text::head_and_tail_lines;
+use base64::{Engine as _, engine::general_purpose};
+use futures::{StreamExt, stream};
 use rmcp::{
-    ErrorData as McpError,
-    handler::server::wrapper::Parameters,
-    model::{CallToolResult, Content},
+    ErrorData as McpError, RoleServer,
+    handler::server::{
+        tool::{IntoCallToolResult, cached_schema_for_type},
+        wrapper::{Json, Parameters},
+    },
+    model::{CallToolResult, Content, ProgressNotificationParam, ProgressToken},
+    service::RequestContext,
     tool, tool_router,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
-#[derive(Deserialize, schemars::JsonSchema)]
+#[derive(Clone, Default, Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum HttpMethod {
+    #[default]
     Get,
     Post,
     Put,
@@ -18,16 +38,26 @@ pub enum HttpMethod {
     Options,
 }
 
-#[derive(Deserialize, schemars::JsonSchema)]
+#[derive(Clone, Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ContentType {
     Json,
     Form,
 }
 
-#[derive(Deserialize, schemars::JsonSchema)]
+#[derive(Clone, Deserialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum MultipartPart {
+    /// A file part read from a local path
+    File { field: String, file_path: String },
+    /// A plain text field
+    Text { field: String, value: String },
+}
+
+#[derive(Clone, Default, Deserialize, schemars::JsonSchema)]
 pub struct FetchParams {
-    /// The URL to fetch
+    /// The URL to fetch. data: URLs are decoded locally; their content is returned as text, or
+    /// base64-encoded when it is not UTF-8.
     url: String,
     /// HTTP method (GET, POST, PUT, DELETE, OPTIONS)
     #[serde(default = "default_method")]
@@ -41,30 +71,1292 @@ pub struct FetchParams {
     /// Body data for POST/PUT requests (can be JSON object or form data)
     #[serde(default)]
     body: Option<serde_json::Value>,
+    /// Form fields sent as an application/x-www-form-urlencoded body, instead of body; cannot
+    /// be combined with parse_json
+    #[serde(default)]
+    form: Option<HashMap<String, String>>,
+    /// Parts sent as a multipart/form-data body, instead of body or form
+    #[serde(default)]
+    multipart: Option<Vec<MultipartPart>>,
+    /// Path of a local file streamed as the body, instead of body, form or multipart. Its
+    /// Content-Type is inferred from the file unless the headers set one.
+    #[serde(default)]
+    body_from_path: Option<String>,
+    /// Response headers to include in the output, matched case-insensitively; use ["*"] for all
+    /// (defaults to content-type, content-length and location)
+    #[serde(default)]
+    include_headers: Option<Vec<String>>,
+    /// ETag of a cached copy, sent as If-None-Match
+    #[serde(default)]
+    if_none_match: Option<String>,
+    /// HTTP date of a cached copy, sent as If-Modified-Since
+    #[serde(default)]
+    if_modified_since: Option<String>,
+    /// Representation to ask the server for, sent as the Accept header (e.g. application/json)
+    #[serde(default)]
+    accept: Option<String>,
+    /// Parse the response body as JSON and return it as a JSON value (default false)
+    #[serde(default)]
+    parse_json: Option<bool>,
+    /// Include every URL visited through redirects in the output (default false)
+    #[serde(default)]
+    include_redirects: Option<bool>,
+    /// Skip TLS certificate verification for this request, for self-signed internal endpoints
+    /// only (default false)
+    #[serde(default)]
+    danger_accept_invalid_certs: Option<bool>,
+    /// Retry up to this many times when the request cannot connect or times out, or the
+    /// server answers 429 or 503, waiting as long as its Retry-After header asks (default 0)
+    #[serde(default)]
+    max_retries: Option<u32>,
+    /// Include how long DNS, connecting, the first byte and the whole request took (default
+    /// false)
+    #[serde(default)]
+    timing: Option<bool>,
+    /// Return the exact body bytes base64-encoded, without any charset decoding (default false)
+    #[serde(default)]
+    raw_base64: Option<bool>,
+    /// Cookies to send, by name, as a Cookie header; cookies the server sets are returned in
+    /// setCookies
+    #[serde(default)]
+    cookies: Option<HashMap<String, String>>,
+    /// First byte of the body to ask for with a Range header, counting from 0 (default 0 when
+    /// range_end is set)
+    #[serde(default)]
+    range_start: Option<u64>,
+    /// Last byte of the body to ask for with a Range header, inclusive (default the end of the
+    /// body)
+    #[serde(default)]
+    range_end: Option<u64>,
+    /// Return the hyperlinks of an HTML page in links, with hrefs resolved to absolute URLs,
+    /// instead of the body (default false)
+    #[serde(default)]
+    extract_links: Option<bool>,
+    /// Regex an absolute href must match to be included in links
+    #[serde(default)]
+    link_pattern: Option<String>,
 }
 
 fn default_method() -> HttpMethod {
     HttpMethod::Get
 }
 
+/// Description of a fetched response
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchResponse {
+    /// HTTP status code
+    status: u16,
+    /// Canonical reason phrase of the status
+    status_text: String,
+    /// Whether the server answered 304 Not Modified
+    not_modified: bool,
+    /// The URL after following redirects
+    final_url: String,
+    /// The selected response headers; repeated headers are joined with ", "
+    headers: HashMap<String, String>,
+    /// Response body as text, parsed JSON with parse_json or base64 with raw_base64; null with
+    /// extract_links
+    body: serde_json::Value,
+    /// Every URL visited, starting with the requested one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect_chain: Option<Vec<String>>,
+    /// How long the request took, when asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timing: Option<FetchTiming>,
+    /// With range_start or range_end, whether the server answered 206 Partial Content. When it
+    /// sent the whole body instead, only the requested bytes of it were read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range_honored: Option<bool>,
+    /// Cookies set by the response's Set-Cookie headers
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    set_cookies: Vec<SetCookie>,
+    /// Hyperlinks of the page in document order, with extract_links
+    #[serde(skip_serializing_if = "Option::is_none")]
+    links: Option<Vec<PageLink>>,
+}
+
+/// A hyperlink of an HTML page
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct PageLink {
+    /// The visible text of the link, without markup
+    text: String,
+    /// The link target as an absolute URL
+    href: String,
+}
+
+/// A cookie set by a Set-Cookie response header
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct SetCookie {
+    name: String,
+    value: String,
+    /// Attributes such as Path, Expires or HttpOnly, keyed by their lowercased name; flags
+    /// have an empty value
+    attributes: HashMap<String, String>,
+}
+
+impl SetCookie {
+    /// Parse a Set-Cookie header value such as `id=42; Path=/; HttpOnly`
+    fn parse(header: &str) -> Option<Self> {
+        let mut parts = header.split(';');
+        let (name, value) = parts.next()?.split_once('=')?;
+        let attributes = parts
+            .filter(|part| !part.trim().is_empty())
+            .map(|part| match part.split_once('=') {
+                Some((key, value)) => (key.trim().to_lowercase(), value.trim().to_string()),
+                None => (part.trim().to_lowercase(), String::new()),
+            })
+            .collect();
+        Some(Self {
+            name: name.trim().to_string(),
+            value: value.trim().trim_matches('"').to_string(),
+            attributes,
+        })
+    }
+}
+
+/// Phase durations of a fetch in milliseconds
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchTiming {
+    /// Host name resolution; null when a pooled connection was reused
+    dns_ms: Option<f64>,
+    /// Opening the connection, including DNS, TCP and TLS; null when a pooled connection was
+    /// reused
+    connect_ms: Option<f64>,
+    /// From sending the request until the response headers arrived
+    time_to_first_byte_ms: f64,
+    /// From sending the request until the whole body was read
+    total_ms: f64,
+}
+
+/// A duration in fractional milliseconds, at microsecond precision
+fn millis(duration: Duration) -> f64 {
+    duration.as_micros() as f64 / 1000.0
+}
+
+/// Response headers reported by fetch when the call does not choose its own
+const DEFAULT_RESPONSE_HEADERS: [&str; 3] = ["content-type", "content-length", "location"];
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct BatchFetchParams {
+    /// The requests to send, each taking the same parameters as fetch
+    requests: Vec<FetchParams>,
+    /// Maximum number of requests in flight at once (defaults to the service setting)
+    #[serde(default)]
+    concurrency: Option<usize>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct FetchPaginatedParams {
+    /// The request for the first page, taking the same parameters as fetch; every page is
+    /// parsed as JSON
+    request: FetchParams,
+    /// JSONPath into the response body of the next page's URL, e.g. "$.links.next"
+    #[serde(default)]
+    next_path: Option<String>,
+    /// Response header holding the next page's URL, instead of next_path
+    #[serde(default)]
+    next_header: Option<String>,
+    /// Follow the rel="next" URL of the Link response header, as GitHub-style APIs send,
+    /// instead of next_path or next_header (default false)
+    #[serde(default)]
+    next_link: Option<bool>,
+    /// JSONPath of the results in each response body (default the whole body); arrays are
+    /// concatenated, other values appended
+    #[serde(default)]
+    items_path: Option<String>,
+    /// Maximum number of pages to fetch (default 10)
+    #[serde(default)]
+    max_pages: Option<usize>,
+    /// Pause between two pages in milliseconds (default 0)
+    #[serde(default)]
+    delay_ms: Option<u64>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct GraphqlParams {
+    /// The URL of the GraphQL endpoint
+    endpoint: String,
+    /// The GraphQL query or mutation document
+    query: String,
+    /// Values of the variables declared by the query
+    #[serde(default)]
+    variables: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Which operation of the document to run, when it holds several
+    #[serde(default)]
+    operation_name: Option<String>,
+    /// Headers to include in the request, such as Authorization
+    #[serde(default)]
+    headers: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct WebSearchParams {
+    /// What to search for
+    query: String,
+    /// Number of results to ask for (default 10)
+    #[serde(default)]
+    count: Option<usize>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct WebSearchOutput {
+    /// The query that was searched
+    query: String,
+    /// The results in the order the search API ranked them
+    results: Vec<SearchResult>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+pub struct SearchResult {
+    title: String,
+    url: String,
+    snippet: String,
+}
+
+/// Field names tried for each part of a search result when the config does not name one
+const SEARCH_FIELD_FALLBACKS: [(&str, &[&str]); 3] = [
+    ("title", &["title", "name"]),
+    ("url", &["url", "link", "href"]),
+    ("snippet", &["snippet", "description", "content", "body"]),
+];
+
+impl SearchResult {
+    /// Read a result from a search API item, using the configured field names
+    fn from_item(item: &serde_json::Value, fields: &HashMap<String, String>) -> Self {
+        let field = |part: &str| {
+            let candidates: Vec<&str> = match fields.get(part) {
+                Some(name) => vec![name.as_str()],
+                None => SEARCH_FIELD_FALLBACKS
+                    .iter()
+                    .find(|(name, _)| *name == part)
+                    .map(|(_, names)| names.to_vec())
+                    .unwrap_or_default(),
+            };
+            candidates
+                .iter()
+                .find_map(|name| select_json_path(item, name)?.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        Self {
+            title: field("title"),
+            url: field("url"),
+            snippet: field("snippet"),
+        }
+    }
+}
+
+/// Decode `%XX` escapes, leaving malformed ones as they are
+fn percent_decode(value: &str) -> Vec<u8> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%'
+            && let Some(hex) = value.get(index + 1..index + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    decoded
+}
+
+/// Decode a `data:[<mediatype>][;base64],<data>` URL into its media type and content
+fn decode_data_url(url: &str) -> Result<(String, Vec<u8>), String> {
+    let rest = &url["data:".len()..];
+    let (header, data) = rest
+        .split_once(',')
+        .ok_or_else(|| "missing ',' before the data".to_string())?;
+    let (media_type, is_base64) = match header.strip_suffix(";base64") {
+        Some(media_type) => (media_type, true),
+        None => (header, false),
+    };
+    let media_type = if media_type.is_empty() {
+        "text/plain;charset=US-ASCII".to_string()
+    } else {
+        media_type.to_string()
+    };
+    let data = percent_decode(data);
+    let content = if is_base64 {
+        let data: Vec<u8> = data
+            .into_iter()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .collect();
+        general_purpose::STANDARD
+            .decode(&data)
+            .map_err(|e| format!("invalid base64: {}", e))?
+    } else {
+        data
+    };
+    Ok((media_type, content))
+}
+
+/// Percent-encode a value for use in a URL query
+fn encode_query_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct WaitForUrlParams {
+    /// The URL to poll with GET requests
+    url: String,
+    /// Status to wait for (default any 2xx status)
+    #[serde(default)]
+    expected_status: Option<u16>,
+    /// How long to keep polling in milliseconds (default 30000)
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Pause between two attempts in milliseconds (default 1000)
+    #[serde(default)]
+    interval_ms: Option<u64>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct RemoteContentTypeParams {
+    /// The URL to inspect
+    url: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteContentType {
+    /// The URL after following redirects
+    final_url: String,
+    /// Status of the response the headers were read from
+    status: u16,
+    /// Request that answered, HEAD or a ranged GET for servers that do not support HEAD
+    method: String,
+    /// The Content-Type header, such as text/html; charset=utf-8
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    /// Size of the full body in bytes, when the server tells
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_length: Option<u64>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct MirrorUrlParams {
+    /// The URL to mirror
+    url: String,
+    /// Download again when the cached copy is older than this many seconds (default: any
+    /// cached copy is used)
+    #[serde(default)]
+    max_age_secs: Option<u64>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorUrlOutput {
+    /// Local path of the copy, the same for every call with this URL
+    path: String,
+    /// Whether an existing copy was used instead of downloading
+    cached: bool,
+    /// Age of the copy in seconds
+    age_secs: u64,
+    /// Size of the copy in bytes
+    size: u64,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct DownloadFileParams {
+    /// The URL to download
+    url: String,
+    /// The path to save the downloaded file to
+    path: String,
+    /// Continue a partial download at the path instead of starting over (default false)
+    #[serde(default)]
+    resume: Option<bool>,
+    /// Include the first and last this many lines of a downloaded text file in the result
+    /// (default none)
+    #[serde(default)]
+    preview_lines: Option<usize>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct DownloadVerifiedParams {
+    /// The URL to download
+    url: String,
+    /// The path to save the downloaded file to
+    path: String,
+    /// The hex-encoded digest the downloaded file must have
+    expected_hash: String,
+    /// Digest algorithm of expected_hash (default sha256)
+    #[serde(default)]
+    algorithm: HashAlgorithm,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct CompareWithRemoteParams {
+    /// The local file to compare
+    path: String,
+    /// The URL whose body the file is compared with
+    url: String,
+    /// Digest algorithm used for both sides (default sha256)
+    #[serde(default)]
+    algorithm: HashAlgorithm,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteComparison {
+    /// Whether the file and the body have the same digest
+    identical: bool,
+    /// Digest algorithm of both digests
+    algorithm: String,
+    /// Size of the local file in bytes
+    local_size: u64,
+    /// Hex-encoded digest of the local file
+    local_digest: String,
+    /// Size of the fetched body in bytes
+    remote_size: u64,
+    /// Hex-encoded digest of the fetched body
+    remote_digest: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct SetDefaultHeadersParams {
+    /// Headers to send with every request of the fetch tools, by name
+    headers: HashMap<String, String>,
+    /// Drop the default headers set before instead of adding to them (default false)
+    #[serde(default)]
+    replace: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct ClearDefaultHeadersParams {
+    /// Names of the default headers to remove, case-insensitively (default all of them)
+    #[serde(default)]
+    names: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct ValidateUrlParams {
+    /// The URL to check
+    url: String,
+}
+
+#[derive(Default, Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlValidation {
+    /// Whether the URL parses
+    valid: bool,
+    /// Why the URL does not parse
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// The URL in normalized form
+    #[serde(skip_serializing_if = "Option::is_none")]
+    normalized: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scheme: Option<String>,
+    /// Whether the scheme is http or https, which the fetch tools request over the network
+    scheme_allowed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<String>,
+    /// Kind of host: domain, ipv4 or ipv6
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host_kind: Option<String>,
+    /// Whether the host is this machine or a private, link-local or otherwise internal
+    /// address
+    internal_host: bool,
+    /// The port, explicit or the scheme's default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fragment: Option<String>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct FetchStatsParams {
+    /// Reset the counters after taking the snapshot (default false)
+    #[serde(default)]
+    reset: Option<bool>,
+}
+
+/// Counters of the HTTP traffic sent by the fetch tools
+#[derive(Default)]
+pub struct FetchStats {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    bytes_received: AtomicU64,
+    cache_hits: AtomicU64,
+    requests_per_host: Mutex<HashMap<String, u64>>,
+}
+
+impl FetchStats {
+    fn record_request(&self, url: &str) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        if let Ok(mut hosts) = self.requests_per_host.lock() {
+            *hosts.entry(host).or_default() += 1;
+        }
+    }
+
+    fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_bytes(&self, bytes: u64) {
+        self.bytes_received.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Count a response served from a cache, such as a 304 Not Modified
+    fn record_cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Describe the counters as JSON, optionally resetting them
+    fn snapshot(&self, reset: bool) -> serde_json::Value {
+        let load = |counter: &AtomicU64| {
+            if reset {
+                counter.swap(0, Ordering::Relaxed)
+            } else {
+                counter.load(Ordering::Relaxed)
+            }
+        };
+        let hosts = match self.requests_per_host.lock() {
+            Ok(mut hosts) if reset => std::mem::take(&mut *hosts),
+            Ok(hosts) => hosts.clone(),
+            Err(_) => HashMap::new(),
+        };
+
+        serde_json::json!({
+            "requests": load(&self.requests),
+            "errors": load(&self.errors),
+            "bytesReceived": load(&self.bytes_received),
+            "cacheHits": load(&self.cache_hits),
+            "requestsPerHost": hosts,
+        })
+    }
+}
+
+/// Caps the requests the fetch tools have in flight to any single host
+pub struct HostLimiter {
+    /// Maximum concurrent requests per host, or None for no limit
+    limit: Option<usize>,
+    semaphores: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HostLimiter {
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            limit: limit.map(|limit| limit.max(1)),
+            semaphores: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for a free slot for the host of `url`; the slot is released when the permit is
+    /// dropped. Returns None when there is no limit or the URL has no host.
+    async fn acquire(&self, url: &str) -> Option<OwnedSemaphorePermit> {
+        let limit = self.limit?;
+        let host = reqwest::Url::parse(url).ok()?.host_str()?.to_lowercase();
+        let semaphore = self
+            .semaphores
+            .lock()
+            .ok()?
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(limit)))
+            .clone();
+        semaphore.acquire_owned().await.ok()
+    }
+}
+
+/// Headers added to every request of the fetch tools, installed at runtime with
+/// `set_default_headers`
+#[derive(Default)]
+pub struct DefaultHeaders {
+    headers: Mutex<HashMap<String, String>>,
+}
+
+/// Set a header on a request, replacing a default header of the same name rather than sending
+/// both
+fn replace_header(
+    request_builder: reqwest::RequestBuilder,
+    name: reqwest::header::HeaderName,
+    value: &str,
+) -> reqwest::RequestBuilder {
+    match reqwest::header::HeaderValue::from_str(value) {
+        Ok(value) => {
+            request_builder.headers(reqwest::header::HeaderMap::from_iter([(name, value)]))
+        }
+        // Left to the builder, which reports the invalid value when the request is sent
+        Err(_) => request_builder.header(name, value),
+    }
+}
+
+impl DefaultHeaders {
+    /// Add the default headers to a request, except those `overridden` by the call's own
+    /// headers, compared case-insensitively. Headers the tool sets afterwards, such as Accept
+    /// or Range, go through `replace_header` so they take the place of a default.
+    fn apply(
+        &self,
+        mut request_builder: reqwest::RequestBuilder,
+        overridden: Option<&HashMap<String, String>>,
+    ) -> reqwest::RequestBuilder {
+        let Ok(headers) = self.headers.lock() else {
+            return request_builder;
+        };
+        for (name, value) in headers.iter() {
+            if overridden.is_some_and(|overridden| {
+                overridden.keys().any(|key| key.eq_ignore_ascii_case(name))
+            }) {
+                continue;
+            }
+            request_builder = request_builder.header(name, value);
+        }
+        request_builder
+    }
+
+    /// Add or replace headers, dropping every earlier one first with `replace`. Returns the
+    /// names of the headers now set.
+    fn set(&self, new_headers: HashMap<String, String>, replace: bool) -> Vec<String> {
+        let Ok(mut headers) = self.headers.lock() else {
+            return Vec::new();
+        };
+        if replace {
+            headers.clear();
+        }
+        for (name, value) in new_headers {
+            headers.retain(|existing, _| !existing.eq_ignore_ascii_case(&name));
+            headers.insert(name, value);
+        }
+        let mut names: Vec<String> = headers.keys().cloned().collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Remove the named headers, or all of them without names. Returns how many were removed
+    /// and how many are left.
+    fn remove(&self, names: Option<&[String]>) -> (usize, usize) {
+        let Ok(mut headers) = self.headers.lock() else {
+            return (0, 0);
+        };
+        let before = headers.len();
+        match names {
+            Some(names) => headers.retain(|existing, _| {
+                !names.iter().any(|name| name.eq_ignore_ascii_case(existing))
+            }),
+            None => headers.clear(),
+        }
+        (before - headers.len(), headers.len())
+    }
+}
+
+/// Maximum number of redirects followed, matching reqwest's default policy
+const MAX_REDIRECTS: usize = 10;
+
+tokio::task_local! {
+    /// URLs redirected to by the request being sent on the current task
+    static REDIRECT_CHAIN: RefCell<Vec<String>>;
+    /// Phase durations of the request being sent on the current task
+    static REQUEST_TIMING: RefCell<RequestTiming>;
+}
+
+/// Durations of the phases of one request attempt, filled in while it is sent
+#[derive(Default)]
+struct RequestTiming {
+    started: Option<Instant>,
+    /// Time spent resolving host names, summed over the connections opened
+    dns: Option<Duration>,
+    /// Time spent opening connections, including DNS and TLS, summed over the connections
+    connect: Option<Duration>,
+}
+
+impl RequestTiming {
+    /// Record a phase duration, when the request is sent inside the `REQUEST_TIMING` scope
+    fn record(phase: fn(&mut RequestTiming) -> &mut Option<Duration>, duration: Duration) {
+        let _ = REQUEST_TIMING.try_with(|timing| {
+            let mut timing = timing.borrow_mut();
+            let total = phase(&mut timing);
+            *total = Some(total.unwrap_or_default() + duration);
+        });
+    }
+}
+
+/// System resolver recording how long lookups take into `REQUEST_TIMING`
+struct TimedResolver;
+
+impl reqwest::dns::Resolve for TimedResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let started = Instant::now();
+            let addrs: Vec<_> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            RequestTiming::record(|timing| &mut timing.dns, started.elapsed());
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Connector wrapper recording how long opening a connection takes into `REQUEST_TIMING`
+#[derive(Clone)]
+struct TimedConnector<S>(S);
+
+impl<S, R> tower::Service<R> for TimedConnector<S>
+where
+    S: tower::Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let started = Instant::now();
+        let connecting = self.0.call(request);
+        Box::pin(async move {
+            let connection = connecting.await;
+            RequestTiming::record(|timing| &mut timing.connect, started.elapsed());
+            connection
+        })
+    }
+}
+
+/// Build the HTTP client used by the fetch tools, optionally skipping TLS certificate checks
+pub fn build_http_client(accept_invalid_certs: bool) -> reqwest::Client {
+    reqwest::Client::builder()
+        .redirect(redirect_policy())
+        .dns_resolver(Arc::new(TimedResolver))
+        .connector_layer(tower::layer::layer_fn(TimedConnector))
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Redirect policy following redirects like reqwest's default one while recording every hop
+/// into `REDIRECT_CHAIN`, when the request is sent inside its scope
+fn redirect_policy() -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(|attempt| {
+        if attempt.previous().len() > MAX_REDIRECTS {
+            return attempt.error("too many redirects");
+        }
+        let _ = REDIRECT_CHAIN.try_with(|chain| chain.borrow_mut().push(attempt.url().to_string()));
+        attempt.follow()
+    })
+}
+
+/// Delay before the first fetch retry, doubled for every further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest wait between two fetch retries; a longer Retry-After ends the retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Parse a `Retry-After` header given either in seconds or as an HTTP date
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Exponential backoff before retry number `attempt` (starting at 0), with full jitter when
+/// enabled so that clients failing together do not retry together
+fn backoff_delay(attempt: u32, jitter: bool) -> Duration {
+    let delay = RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_RETRY_DELAY);
+    if jitter {
+        delay.mul_f64(rand::random::<f64>())
+    } else {
+        delay
+    }
+}
+
+/// Number of downloaded bytes between two progress notifications
+const PROGRESS_INTERVAL_BYTES: u64 = 1024 * 1024;
+
+/// Report download progress to the client, ignoring delivery failures
+async fn notify_download_progress(
+    context: &RequestContext<RoleServer>,
+    progress_token: &ProgressToken,
+    downloaded: u64,
+    total: Option<u64>,
+) {
+    let _ = context
+        .peer
+        .notify_progress(ProgressNotificationParam {
+            progress_token: progress_token.clone(),
+            progress: downloaded as f64,
+            total: total.map(|total| total as f64),
+            message: None,
+        })
+        .await;
+}
+
+/// Result returned when the client cancels a tool call before it finished
+pub fn cancelled_result(operation: &str) -> CallToolResult {
+    CallToolResult::error(vec![Content::text(format!(
+        "{} cancelled by the client",
+        operation
+    ))])
+}
+
+/// Size of a full body from a response's headers; a ranged response carries it in
+/// Content-Range, such as `bytes 0-0/1234`
+fn full_content_length(response: &reqwest::Response) -> Option<u64> {
+    let headers = response.headers();
+    if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+        return content_range_total(headers);
+    }
+    headers
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Cache path of a URL: the SHA-256 of the URL, keeping the extension of its path so the
+/// copy is recognized by type
+fn mirror_path(cache_dir: &std::path::Path, url: &str) -> std::path::PathBuf {
+    use sha2::{Digest, Sha256};
+    let mut name = to_hex(&Sha256::digest(url.as_bytes()));
+    if let Ok(parsed) = reqwest::Url::parse(url)
+        && let Some(extension) = std::path::Path::new(parsed.path()).extension()
+        && let Some(extension) = extension.to_str()
+        && extension.len() <= 10
+        && extension.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        name = format!("{}.{}", name, extension);
+    }
+    cache_dir.join(name)
+}
+
+/// Digest a response body chunk by chunk, returning the hex digest and the body size
+async fn digest_response<D: sha2::Digest>(
+    mut response: reqwest::Response,
+) -> reqwest::Result<(String, u64)> {
+    let mut hasher = D::new();
+    let mut size = 0;
+    while let Some(chunk) = response.chunk().await? {
+        size += chunk.len() as u64;
+        hasher.update(&chunk);
+    }
+    Ok((to_hex(&hasher.finalize()), size))
+}
+
+/// Schemes the fetch tools send over the network
+const NETWORK_SCHEMES: [&str; 2] = ["http", "https"];
+
+/// Whether a host is this machine or an address not reachable from the public internet:
+/// localhost, loopback, private, link-local, shared, unspecified or unique local addresses
+fn is_internal_host(host: &url::Host<&str>) -> bool {
+    let internal_ipv4 = |ip: &std::net::Ipv4Addr| {
+        ip.is_loopback()
+            || ip.is_private()
+            || ip.is_link_local()
+            || ip.is_unspecified()
+            || ip.is_broadcast()
+            // Shared address space of carrier-grade NAT, 100.64.0.0/10
+            || (ip.octets()[0] == 100 && (ip.octets()[1] & 0xc0) == 64)
+    };
+    match host {
+        url::Host::Domain(domain) => {
+            let domain = domain.trim_end_matches('.').to_lowercase();
+            domain == "localhost" || domain.ends_with(".localhost")
+        }
+        url::Host::Ipv4(ip) => internal_ipv4(ip),
+        url::Host::Ipv6(ip) => {
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()
+                || ip.to_ipv4_mapped().is_some_and(|ip| internal_ipv4(&ip))
+        }
+    }
+}
+
+/// The target of the link with a relation in a Link header, such as
+/// `<https://api.example.com/items?page=2>; rel="next", <...>; rel="last"`
+fn link_with_rel(header: &str, rel: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let mut parts = link.split(';');
+        let target = parts.next()?.trim().strip_prefix('<')?.strip_suffix('>')?;
+        parts
+            .filter_map(|param| param.split_once('='))
+            .filter(|(name, _)| name.trim().eq_ignore_ascii_case("rel"))
+            .any(|(_, value)| {
+                value
+                    .trim()
+                    .trim_matches('"')
+                    .split_whitespace()
+                    .any(|value| value.eq_ignore_ascii_case(rel))
+            })
+            .then(|| target.to_string())
+    })
+}
+
+/// Content types of common file extensions, for uploads with body_from_path
+const EXTENSION_CONTENT_TYPES: [(&str, &str); 12] = [
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("csv", "text/csv"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("js", "text/javascript"),
+    ("css", "text/css"),
+    ("svg", "image/svg+xml"),
+];
+
+/// Infer the content type of a file from its extension, or else from its leading bytes
+async fn infer_content_type(path: &str) -> String {
+    let extension = std::path::Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    if let Some((_, content_type)) = EXTENSION_CONTENT_TYPES
+        .iter()
+        .find(|(known, _)| extension.as_deref() == Some(*known))
+    {
+        return content_type.to_string();
+    }
+    let mut head = [0u8; 512];
+    let read = match tokio::fs::File::open(path).await {
+        Ok(mut file) => tokio::io::AsyncReadExt::read(&mut file, &mut head)
+            .await
+            .unwrap_or(0),
+        Err(_) => 0,
+    };
+    detect_mime(&head[..read]).to_string()
+}
+
+/// Decode the character references of HTML text, such as `&amp;` and `&#8217;`
+fn decode_html_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let name = &rest[1..end];
+            let c = match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => match name.strip_prefix('#') {
+                    Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16)
+                        .ok()
+                        .and_then(char::from_u32),
+                    Some(decimal) => decimal.parse().ok().and_then(char::from_u32),
+                    None => None,
+                },
+            };
+            c.map(|c| (c, end))
+        });
+        match reference {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// An `<a>` element with its quoted or unquoted href and inner HTML
+const ANCHOR_PATTERN: &str =
+    r#"(?is)<a\s[^>]*?\bhref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))[^>]*>(.*?)</a\s*>"#;
+
+/// The href of a `<base>` element, which relative links resolve against
+const BASE_PATTERN: &str = r#"(?is)<base\s[^>]*?\bhref\s*=\s*["']?([^"'\s>]+)"#;
+
+/// The `<a href>` links of an HTML page, resolved against its `<base href>` or else its URL.
+/// Script links are skipped, and with a pattern only matching hrefs are kept.
+fn extract_page_links(html: &str, page_url: &str, pattern: Option<&regex::Regex>) -> Vec<PageLink> {
+    let anchor = regex::Regex::new(ANCHOR_PATTERN).expect("Invalid anchor pattern");
+    let base_tag = regex::Regex::new(BASE_PATTERN).expect("Invalid base pattern");
+    let tag = regex::Regex::new(r"(?s)<[^>]*>").expect("Invalid tag pattern");
+
+    let Ok(page_url) = reqwest::Url::parse(page_url) else {
+        return Vec::new();
+    };
+    let base = base_tag
+        .captures(html)
+        .and_then(|captures| page_url.join(&decode_html_entities(&captures[1])).ok())
+        .unwrap_or(page_url);
+
+    anchor
+        .captures_iter(html)
+        .filter_map(|captures| {
+            let href = captures
+                .get(1)
+                .or_else(|| captures.get(2))
+                .or_else(|| captures.get(3))?
+                .as_str();
+            let href = decode_html_entities(href.trim());
+            if href.is_empty()
+                || href
+                    .get(..11)
+                    .is_some_and(|scheme| scheme.eq_ignore_ascii_case("javascript:"))
+            {
+                return None;
+            }
+            let href = base.join(&href).ok()?.to_string();
+            if pattern.is_some_and(|pattern| !pattern.is_match(&href)) {
+                return None;
+            }
+            let text = tag.replace_all(&captures[4], " ");
+            let text = decode_html_entities(&text)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            Some(PageLink { text, href })
+        })
+        .collect()
+}
+
+/// Answer a fetch of a data: URL from the URL itself, without a network request
+fn data_url_response(params: &FetchParams) -> Result<FetchResponse, McpError> {
+    let (media_type, content) = decode_data_url(&params.url).map_err(|e| {
+        McpError::new(
+            rmcp::model::ErrorCode::INVALID_PARAMS,
+            format!("Invalid data: URL: {}", e),
+            None,
+        )
+    })?;
+    let text = if params.raw_base64.unwrap_or(false) {
+        None
+    } else {
+        String::from_utf8(content.clone()).ok()
+    };
+    let body = match text {
+        Some(text) if params.parse_json.unwrap_or(false) => {
+            serde_json::from_str(&text).map_err(|e| {
+                McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Response body is not valid JSON: {}", e),
+                    None,
+                )
+            })?
+        }
+        Some(text) => serde_json::Value::String(text),
+        None => serde_json::Value::String(general_purpose::STANDARD.encode(&content)),
+    };
+    Ok(FetchResponse {
+        status: 200,
+        status_text: "OK".to_string(),
+        not_modified: false,
+        final_url: params.url.clone(),
+        headers: HashMap::from([
+            ("content-type".to_string(), media_type),
+            ("content-length".to_string(), content.len().to_string()),
+        ]),
+        body,
+        redirect_chain: None,
+        timing: None,
+        range_honored: None,
+        set_cookies: Vec::new(),
+        links: None,
+    })
+}
+
+/// Read only the bytes `start..=end` of a response body, for servers that ignore the Range
+/// header; the rest of the body is not downloaded
+async fn read_byte_range(
+    mut response: reqwest::Response,
+    start: u64,
+    end: Option<u64>,
+) -> Result<Vec<u8>, reqwest::Error> {
+    // Offsets beyond the address space saturate: such a body could never be held anyway
+    let limit = end.map(|end| {
+        usize::try_from(end)
+            .ok()
+            .and_then(|end| end.checked_add(1))
+            .unwrap_or(usize::MAX)
+    });
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        if limit.is_some_and(|limit| bytes.len() >= limit) {
+            break;
+        }
+    }
+    if let Some(limit) = limit {
+        bytes.truncate(limit);
+    }
+    let start = usize::try_from(start).unwrap_or(usize::MAX);
+    bytes.drain(..start.min(bytes.len()));
+    Ok(bytes)
+}
+
+/// Characters of a response body quoted in an error message
+const MAX_ERROR_BODY_CHARS: usize = 500;
+
+/// The start of a response body, for quoting it in an error message
+fn truncate_body(body: &str) -> String {
+    match body.char_indices().nth(MAX_ERROR_BODY_CHARS) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+/// Describe the first and last lines of a downloaded file, for the download result
+async fn download_preview(path: &str, count: usize) -> String {
+    match is_binary_file(path).await {
+        Ok(false) => {}
+        Ok(true) => return "\n\nNo preview: the file is binary".to_string(),
+        Err(e) => return format!("\n\nNo preview: {}", e),
+    }
+    match head_and_tail_lines(path, count).await {
+        Ok((head, tail)) if tail.is_empty() => format!("\n\nContent:\n{}", head.join("\n")),
+        Ok((head, tail)) => format!(
+            "\n\nFirst {} lines:\n{}\n\nLast {} lines:\n{}",
+            head.len(),
+            head.join("\n"),
+            tail.len(),
+            tail.join("\n")
+        ),
+        Err(e) => format!("\n\nNo preview: {}", e),
+    }
+}
+
+/// Parse the total length out of a `Content-Range: bytes start-end/total` header
+fn content_range_total(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    headers
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
 #[tool_router(router = tool_router_fetch, vis = "pub")]
 impl DiveDefaultService {
-    #[tool(description = "Make HTTP requests with support for different methods and content types")]
+    #[tool(
+        description = "Make HTTP requests with support for different methods and content types",
+        annotations(read_only_hint = false, open_world_hint = true)
+    )]
     pub async fn fetch(
         &self,
         Parameters(params): Parameters<FetchParams>,
-    ) -> Result<CallToolResult, McpError> {
+    ) -> Result<Json<FetchResponse>, McpError> {
+        self.send_fetch(params).await.map(Json)
+    }
+
+    /// Send a fetch request and describe the response
+    async fn send_fetch(&self, params: FetchParams) -> Result<FetchResponse, McpError> {
+        let body_kinds = [
+            params.body.is_some(),
+            params.form.is_some(),
+            params.multipart.is_some(),
+            params.body_from_path.is_some(),
+        ];
+        if body_kinds.iter().filter(|&&set| set).count() > 1 {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "Only one of body, form, multipart and body_from_path can be provided".to_string(),
+                None,
+            ));
+        }
+        if params.raw_base64.unwrap_or(false) && params.parse_json.unwrap_or(false) {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "raw_base64 and parse_json cannot be combined".to_string(),
+                None,
+            ));
+        }
+        if params.form.is_some() && params.parse_json.unwrap_or(false) {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "form and parse_json cannot be combined".to_string(),
+                None,
+            ));
+        }
+
+        let link_pattern = match params.link_pattern.as_deref().map(regex::Regex::new) {
+            Some(Ok(pattern)) => Some(pattern),
+            Some(Err(e)) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid link_pattern: {}", e),
+                    None,
+                ));
+            }
+            None => None,
+        };
+
+        if params
+            .url
+            .get(..5)
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("data:"))
+        {
+            return data_url_response(&params);
+        }
+
+        let http_client = if params.danger_accept_invalid_certs.unwrap_or(false) {
+            eprintln!(
+                "warning: fetching {} without TLS certificate verification",
+                params.url
+            );
+            &self.insecure_http_client
+        } else {
+            &self.http_client
+        };
+
         // Build the request based on method
         let mut request_builder = match params.method {
-            HttpMethod::Get => self.http_client.get(&params.url),
-            HttpMethod::Post => self.http_client.post(&params.url),
-            HttpMethod::Put => self.http_client.put(&params.url),
-            HttpMethod::Delete => self.http_client.delete(&params.url),
-            HttpMethod::Options => self
-                .http_client
-                .request(reqwest::Method::OPTIONS, &params.url),
+            HttpMethod::Get => http_client.get(&params.url),
+            HttpMethod::Post => http_client.post(&params.url),
+            HttpMethod::Put => http_client.put(&params.url),
+            HttpMethod::Delete => http_client.delete(&params.url),
+            HttpMethod::Options => http_client.request(reqwest::Method::OPTIONS, &params.url),
         };
 
+        let has_content_type = params.headers.as_ref().is_some_and(|headers| {
+            headers
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case("content-type"))
+        });
+
+        request_builder = self
+            .default_headers
+            .apply(request_builder, params.headers.as_ref());
+
         // Add headers if provided
         if let Some(headers) = params.headers {
             for (key, value) in headers {
@@ -72,6 +1364,54 @@ impl DiveDefaultService {
             }
         }
 
+        if let Some(accept) = params.accept {
+            request_builder = replace_header(request_builder, reqwest::header::ACCEPT, &accept);
+        }
+
+        let range = match (params.range_start, params.range_end) {
+            (None, None) => None,
+            (start, end) => {
+                let start = start.unwrap_or(0);
+                if end.is_some_and(|end| end < start) {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INVALID_PARAMS,
+                        "range_end must not be before range_start".to_string(),
+                        None,
+                    ));
+                }
+                let end_text = end.map(|end| end.to_string()).unwrap_or_default();
+                request_builder = replace_header(
+                    request_builder,
+                    reqwest::header::RANGE,
+                    &format!("bytes={}-{}", start, end_text),
+                );
+                Some((start, end))
+            }
+        };
+
+        if let Some(cookies) = params.cookies {
+            let mut cookies: Vec<String> = cookies
+                .into_iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect();
+            cookies.sort();
+            request_builder = replace_header(
+                request_builder,
+                reqwest::header::COOKIE,
+                &cookies.join("; "),
+            );
+        }
+
+        // Add conditional request headers if provided
+        if let Some(etag) = params.if_none_match {
+            request_builder =
+                replace_header(request_builder, reqwest::header::IF_NONE_MATCH, &etag);
+        }
+        if let Some(date) = params.if_modified_since {
+            request_builder =
+                replace_header(request_builder, reqwest::header::IF_MODIFIED_SINCE, &date);
+        }
+
         // Add body if provided
         if let Some(body) = params.body {
             match params.content_type {
@@ -102,43 +1442,1297 @@ impl DiveDefaultService {
             }
         }
 
-        // Send the request
-        match request_builder.send().await {
+        // Add form fields if provided
+        if let Some(form) = params.form {
+            request_builder = request_builder.form(&form);
+        }
+
+        // Add multipart parts if provided
+        if let Some(parts) = params.multipart {
+            let mut multipart_form = reqwest::multipart::Form::new();
+            for part in parts {
+                multipart_form = match part {
+                    MultipartPart::Text { field, value } => multipart_form.text(field, value),
+                    MultipartPart::File { field, file_path } => {
+                        let bytes = match tokio::fs::read(&file_path).await {
+                            Ok(bytes) => bytes,
+                            Err(e) => {
+                                return Err(McpError::new(
+                                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                                    format!("Failed to read file {}: {}", file_path, e),
+                                    None,
+                                ));
+                            }
+                        };
+                        let mut file_part = reqwest::multipart::Part::bytes(bytes);
+                        if let Some(file_name) = std::path::Path::new(&file_path).file_name() {
+                            file_part =
+                                file_part.file_name(file_name.to_string_lossy().into_owned());
+                        }
+                        multipart_form.part(field, file_part)
+                    }
+                };
+            }
+            request_builder = request_builder.multipart(multipart_form);
+        }
+
+        // Stream a local file as the body; such a request cannot be retried
+        if let Some(path) = params.body_from_path {
+            let file = match tokio::fs::File::open(&path).await {
+                Ok(file) => file,
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to read file {}: {}", path, e),
+                        None,
+                    ));
+                }
+            };
+            if let Ok(metadata) = file.metadata().await {
+                request_builder = replace_header(
+                    request_builder,
+                    reqwest::header::CONTENT_LENGTH,
+                    &metadata.len().to_string(),
+                );
+            }
+            if !has_content_type {
+                request_builder = replace_header(
+                    request_builder,
+                    reqwest::header::CONTENT_TYPE,
+                    &infer_content_type(&path).await,
+                );
+            }
+            request_builder = request_builder.body(file);
+        }
+
+        let include_headers: Vec<String> = match params.include_headers {
+            Some(names) => names.iter().map(|name| name.to_lowercase()).collect(),
+            None => DEFAULT_RESPONSE_HEADERS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        };
+        let include_all_headers = include_headers.iter().any(|name| name == "*");
+
+        // Send the request, recording the redirects it follows
+        let _host_permit = self.host_limiter.acquire(&params.url).await;
+        self.fetch_stats.record_request(&params.url);
+        let (response, redirects, timing) = REDIRECT_CHAIN
+            .scope(
+                RefCell::new(Vec::new()),
+                REQUEST_TIMING.scope(RefCell::default(), async {
+                    let response = self
+                        .send_with_retries(request_builder, params.max_retries.unwrap_or(0))
+                        .await;
+                    let timing = REQUEST_TIMING.with(|timing| timing.take());
+                    let time_to_first_byte = timing.started.map(|started| started.elapsed());
+                    (
+                        response,
+                        REDIRECT_CHAIN.with(|chain| chain.take()),
+                        (timing, time_to_first_byte),
+                    )
+                }),
+            )
+            .await;
+        match response {
             Ok(response) => {
                 let status = response.status();
-                let headers: HashMap<String, String> = response
+                if status == reqwest::StatusCode::NOT_MODIFIED {
+                    self.fetch_stats.record_cache_hit();
+                }
+                let final_url = response.url().to_string();
+                // A header sent several times, such as Link, is joined into one value
+                let mut headers: HashMap<String, String> = HashMap::new();
+                for (k, v) in response.headers().iter().filter(|(k, _)| {
+                    include_all_headers || include_headers.iter().any(|name| name == k.as_str())
+                }) {
+                    let v = v.to_str().unwrap_or("");
+                    headers
+                        .entry(k.to_string())
+                        .and_modify(|value| {
+                            value.push_str(", ");
+                            value.push_str(v);
+                        })
+                        .or_insert_with(|| v.to_string());
+                }
+                let set_cookies = response
                     .headers()
+                    .get_all(reqwest::header::SET_COOKIE)
                     .iter()
-                    .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+                    .filter_map(|value| SetCookie::parse(value.to_str().ok()?))
                     .collect();
 
-                match response.text().await {
-                    Ok(body) => {
-                        let result = serde_json::json!({
-                            "status": status.as_u16(),
-                            "statusText": status.canonical_reason().unwrap_or(""),
-                            "headers": headers,
-                            "body": body,
+                let range_honored = range.map(|_| status == reqwest::StatusCode::PARTIAL_CONTENT);
+                let body = if let Some((start, end)) = range
+                    && range_honored == Some(false)
+                    && status.is_success()
+                {
+                    read_byte_range(response, start, end).await.map(|bytes| {
+                        let body = if params.raw_base64.unwrap_or(false) {
+                            general_purpose::STANDARD.encode(&bytes)
+                        } else {
+                            String::from_utf8_lossy(&bytes).into_owned()
+                        };
+                        (bytes.len(), body)
+                    })
+                } else if params.raw_base64.unwrap_or(false) {
+                    response
+                        .bytes()
+                        .await
+                        .map(|bytes| (bytes.len(), general_purpose::STANDARD.encode(&bytes)))
+                } else {
+                    response.text().await.map(|text| (text.len(), text))
+                };
+                match body {
+                    Ok((received, body)) => {
+                        self.fetch_stats.record_bytes(received as u64);
+                        let links = params
+                            .extract_links
+                            .unwrap_or(false)
+                            .then(|| extract_page_links(&body, &final_url, link_pattern.as_ref()));
+                        let body = if links.is_some() {
+                            serde_json::Value::Null
+                        } else if params.parse_json.unwrap_or(false) {
+                            match serde_json::from_str(&body) {
+                                Ok(json) => json,
+                                Err(e) => {
+                                    return Err(McpError::new(
+                                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                                        format!("Response body is not valid JSON: {}", e),
+                                        None,
+                                    ));
+                                }
+                            }
+                        } else {
+                            serde_json::Value::String(body)
+                        };
+                        let redirect_chain = params.include_redirects.unwrap_or(false).then(|| {
+                            let mut chain = vec![params.url.clone()];
+                            chain.extend(redirects);
+                            chain
                         });
+                        let (timing, time_to_first_byte) = timing;
+                        let timing = match (
+                            params.timing.unwrap_or(false),
+                            timing.started,
+                            time_to_first_byte,
+                        ) {
+                            (true, Some(started), Some(time_to_first_byte)) => Some(FetchTiming {
+                                dns_ms: timing.dns.map(millis),
+                                connect_ms: timing.connect.map(millis),
+                                time_to_first_byte_ms: millis(time_to_first_byte),
+                                total_ms: millis(started.elapsed()),
+                            }),
+                            _ => None,
+                        };
+                        Ok(FetchResponse {
+                            status: status.as_u16(),
+                            status_text: status.canonical_reason().unwrap_or("").to_string(),
+                            not_modified: status == reqwest::StatusCode::NOT_MODIFIED,
+                            final_url,
+                            headers,
+                            body,
+                            redirect_chain,
+                            timing,
+                            range_honored,
+                            set_cookies,
+                            links,
+                        })
+                    }
+                    Err(e) => {
+                        self.fetch_stats.record_error();
+                        Err(McpError::new(
+                            rmcp::model::ErrorCode::INTERNAL_ERROR,
+                            format!("Failed to read response body: {}", e),
+                            None,
+                        ))
+                    }
+                }
+            }
+            Err(e) => {
+                self.fetch_stats.record_error();
+                Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to send request: {}", e),
+                    None,
+                ))
+            }
+        }
+    }
+
+    /// Send a request, retrying transient failures with backoff. Requests whose body cannot
+    /// be cloned, such as multipart uploads, are only sent once.
+    async fn send_with_retries(
+        &self,
+        mut request_builder: reqwest::RequestBuilder,
+        max_retries: u32,
+    ) -> reqwest::Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            let retry_builder = if attempt < max_retries {
+                request_builder.try_clone()
+            } else {
+                None
+            };
+            // Only the redirects and timing of the last attempt are reported
+            REDIRECT_CHAIN.with(|chain| chain.borrow_mut().clear());
+            let _ = REQUEST_TIMING.try_with(|timing| {
+                *timing.borrow_mut() = RequestTiming {
+                    started: Some(Instant::now()),
+                    ..Default::default()
+                }
+            });
+            let response = request_builder.send().await;
+            let Some(next_builder) = retry_builder else {
+                return response;
+            };
+
+            let delay = match &response {
+                Ok(response)
+                    if matches!(
+                        response.status(),
+                        reqwest::StatusCode::TOO_MANY_REQUESTS
+                            | reqwest::StatusCode::SERVICE_UNAVAILABLE
+                    ) =>
+                {
+                    match retry_after(response.headers()) {
+                        Some(delay) if delay > MAX_RETRY_DELAY => None,
+                        Some(delay) => Some(delay),
+                        None => Some(backoff_delay(attempt, self.config.retry_jitter)),
+                    }
+                }
+                Err(e) if e.is_connect() || e.is_timeout() => {
+                    Some(backoff_delay(attempt, self.config.retry_jitter))
+                }
+                _ => None,
+            };
+            let Some(delay) = delay else {
+                return response;
+            };
+
+            tokio::time::sleep(delay).await;
+            request_builder = next_builder;
+            attempt += 1;
+        }
+    }
+
+    #[tool(
+        description = "Get a snapshot of the request, error, byte and cache hit counters of the fetch tools",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn fetch_stats(
+        &self,
+        Parameters(params): Parameters<FetchStatsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self.fetch_stats.snapshot(params.reset.unwrap_or(false));
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Send several HTTP requests concurrently and return their results in order",
+        annotations(read_only_hint = false, open_world_hint = true)
+    )]
+    pub async fn batch_fetch(
+        &self,
+        Parameters(params): Parameters<BatchFetchParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let concurrency = self.batch_concurrency(params.concurrency);
+        let batch = stream::iter(params.requests)
+            .map(|request| self.send_fetch(request))
+            .buffered(concurrency)
+            .map(|result| match result {
+                Ok(response) => serde_json::to_value(response)
+                    .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() })),
+                Err(e) => serde_json::json!({ "error": e.message }),
+            })
+            .collect::<Vec<_>>();
+        // Dropping the batch aborts the requests still in flight
+        let results = tokio::select! {
+            results = batch => results,
+            _ = context.ct.cancelled() => return Ok(cancelled_result("Batch fetch")),
+        };
+
+        let result = serde_json::Value::Array(results);
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Fetch a paginated JSON API, following next links from the body, a header or the Link header and concatenating the results; nextUrl is the page to continue from when it stopped early",
+        annotations(read_only_hint = false, open_world_hint = true)
+    )]
+    pub async fn fetch_paginated(
+        &self,
+        Parameters(params): Parameters<FetchPaginatedParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let next_link = params.next_link.unwrap_or(false);
+        let next_header = match (&params.next_path, &params.next_header, next_link) {
+            (Some(_), None, false) => None,
+            (None, Some(header), false) => Some(header.to_lowercase()),
+            (None, None, true) => Some("link".to_string()),
+            _ => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    "Exactly one of next_path, next_header and next_link must be provided"
+                        .to_string(),
+                    None,
+                ));
+            }
+        };
+        let max_pages = params.max_pages.unwrap_or(10).max(1);
+        let mut request = params.request;
+        request.parse_json = Some(true);
+        if let Some(header) = &next_header {
+            request
+                .include_headers
+                .get_or_insert_with(|| {
+                    DEFAULT_RESPONSE_HEADERS
+                        .iter()
+                        .map(|name| name.to_string())
+                        .collect()
+                })
+                .push(header.clone());
+        }
+
+        let mut items = Vec::new();
+        let mut pages = 0;
+        let mut visited = Vec::new();
+        let mut next_url = Some(request.url.clone());
+        let mut error = None;
+        while let Some(url) = next_url.take() {
+            if pages == max_pages {
+                next_url = Some(url);
+                break;
+            }
+            if pages > 0
+                && let Some(delay_ms) = params.delay_ms
+            {
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            }
+            let mut page_request = request.clone();
+            page_request.url = url.clone();
+            visited.push(url);
+
+            let response = tokio::select! {
+                response = self.send_fetch(page_request) => response,
+                _ = context.ct.cancelled() => return Ok(cancelled_result("Paginated fetch")),
+            };
+            let response = match response {
+                Ok(response) if (200..300).contains(&response.status) => response,
+                Ok(response) => {
+                    error = Some(format!(
+                        "Page {} returned status {}",
+                        pages + 1,
+                        response.status
+                    ));
+                    next_url = visited.pop();
+                    break;
+                }
+                Err(e) => {
+                    error = Some(format!("Page {} failed: {}", pages + 1, e.message));
+                    next_url = visited.pop();
+                    break;
+                }
+            };
+            pages += 1;
+
+            let page_items = match &params.items_path {
+                Some(path) => select_json_path(&response.body, path).cloned(),
+                None => Some(response.body.clone()),
+            };
+            match page_items {
+                Some(serde_json::Value::Array(page_items)) => items.extend(page_items),
+                Some(serde_json::Value::Null) | None => {}
+                Some(item) => items.push(item),
+            }
+
+            let next = match (&params.next_path, &next_header) {
+                (Some(path), _) => select_json_path(&response.body, path)
+                    .and_then(|next| next.as_str())
+                    .map(str::to_string),
+                (None, Some(header)) if next_link => response
+                    .headers
+                    .get(header)
+                    .and_then(|links| link_with_rel(links, "next")),
+                (None, Some(header)) => response.headers.get(header).cloned(),
+                (None, None) => None,
+            };
+            // Relative links are resolved against the page they were found on
+            next_url = next
+                .filter(|next| !next.is_empty())
+                .and_then(|next| {
+                    reqwest::Url::parse(&response.final_url)
+                        .and_then(|base| base.join(&next))
+                        .ok()
+                })
+                .map(|next| next.to_string())
+                .filter(|next| !visited.contains(next));
+        }
+
+        let mut result = serde_json::json!({
+            "items": items,
+            "pages": pages,
+            "nextUrl": next_url,
+        });
+        if let Some(error) = error {
+            result["error"] = error.into();
+        }
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Download a URL to a local file, optionally resuming a partial download",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            open_world_hint = true
+        )
+    )]
+    pub async fn download_file(
+        &self,
+        Parameters(params): Parameters<DownloadFileParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // Progress is only reported to clients that asked for it
+        let progress_token = context.meta.get_progress_token();
+
+        let mut offset = 0;
+        if params.resume.unwrap_or(false)
+            && let Ok(metadata) = tokio::fs::metadata(&params.path).await
+        {
+            offset = metadata.len();
+        }
+
+        // Hold a slot for the host until the whole body has been written
+        let _host_permit = tokio::select! {
+            permit = self.host_limiter.acquire(&params.url) => permit,
+            _ = context.ct.cancelled() => return Ok(cancelled_result("Download")),
+        };
+
+        // Only ask for a range when the server advertises support for it
+        if offset > 0 {
+            let head = self
+                .default_headers
+                .apply(self.http_client.head(&params.url), None);
+            let accepts_ranges = match head.send().await {
+                Ok(response) => response
+                    .headers()
+                    .get(reqwest::header::ACCEPT_RANGES)
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(|v| v.eq_ignore_ascii_case("bytes")),
+                Err(_) => false,
+            };
+            if !accepts_ranges {
+                offset = 0;
+            }
+        }
+
+        let mut request_builder = self
+            .default_headers
+            .apply(self.http_client.get(&params.url), None);
+        if offset > 0 {
+            request_builder = replace_header(
+                request_builder,
+                reqwest::header::RANGE,
+                &format!("bytes={}-", offset),
+            );
+        }
+
+        self.fetch_stats.record_request(&params.url);
+        let response = tokio::select! {
+            response = request_builder.send() => response,
+            _ = context.ct.cancelled() => return Ok(cancelled_result("Download")),
+        };
+        let mut response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                self.fetch_stats.record_error();
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to send request: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let status = response.status();
+        // The partial file already holds everything the server has
+        if offset > 0
+            && status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE
+            && content_range_total(response.headers()) == Some(offset)
+        {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "File already fully downloaded: {} ({} bytes)",
+                params.path, offset
+            ))]));
+        }
+        if !status.is_success() {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Download failed with status {}", status),
+                None,
+            ));
+        }
+
+        // A plain 200 means the server ignored the range, so start over
+        let resumed = status == reqwest::StatusCode::PARTIAL_CONTENT;
+        if !resumed {
+            offset = 0;
+        }
+        let expected_size = if resumed {
+            content_range_total(response.headers())
+        } else {
+            response.content_length()
+        };
+        let remaining = expected_size.map_or(0, |size| size.saturating_sub(offset));
+        self.ensure_free_space(&params.path, remaining)?;
+
+        let file = if resumed {
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&params.path)
+                .await
+        } else {
+            tokio::fs::File::create(&params.path).await
+        };
+        let mut file = match file {
+            Ok(file) => file,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to open file: {}", e),
+                    None,
+                ));
+            }
+        };
 
-                        Ok(CallToolResult::success(vec![Content::text(
-                            serde_json::to_string_pretty(&result)
-                                .unwrap_or_else(|_| result.to_string()),
-                        )]))
+        let mut total_size = offset;
+        let mut reported_size = offset;
+        loop {
+            let chunk = tokio::select! {
+                chunk = response.chunk() => chunk,
+                _ = context.ct.cancelled() => {
+                    // Leave the file as it was before this call
+                    if resumed {
+                        let _ = file.set_len(offset).await;
+                    } else {
+                        drop(file);
+                        let _ = tokio::fs::remove_file(&params.path).await;
+                    }
+                    return Ok(cancelled_result("Download"));
+                }
+            };
+            match chunk {
+                Ok(Some(chunk)) => {
+                    if let Err(e) = file.write_all(&chunk).await {
+                        return Err(McpError::new(
+                            rmcp::model::ErrorCode::INTERNAL_ERROR,
+                            format!("Failed to write file: {}", e),
+                            None,
+                        ));
+                    }
+                    total_size += chunk.len() as u64;
+                    self.fetch_stats.record_bytes(chunk.len() as u64);
+                    if let Some(progress_token) = &progress_token
+                        && total_size - reported_size >= PROGRESS_INTERVAL_BYTES
+                    {
+                        reported_size = total_size;
+                        notify_download_progress(
+                            &context,
+                            progress_token,
+                            total_size,
+                            expected_size,
+                        )
+                        .await;
                     }
-                    Err(e) => Err(McpError::new(
+                }
+                Ok(None) => {
+                    if let Some(progress_token) = &progress_token {
+                        notify_download_progress(
+                            &context,
+                            progress_token,
+                            total_size,
+                            expected_size,
+                        )
+                        .await;
+                    }
+                    break;
+                }
+                Err(e) => {
+                    self.fetch_stats.record_error();
+                    return Err(McpError::new(
                         rmcp::model::ErrorCode::INTERNAL_ERROR,
-                        format!("Failed to read response body: {}", e),
+                        format!(
+                            "Download interrupted after {} bytes, retry with resume: {}",
+                            total_size, e
+                        ),
                         None,
-                    )),
+                    ));
                 }
             }
-            Err(e) => Err(McpError::new(
+        }
+        if let Err(e) = file.flush().await {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to write file: {}", e),
+                None,
+            ));
+        }
+
+        if let Some(expected_size) = expected_size
+            && total_size != expected_size
+        {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Downloaded size {} does not match expected size {}, retry with resume",
+                    total_size, expected_size
+                ),
+                None,
+            ));
+        }
+
+        let mut message = if resumed {
+            format!(
+                "Successfully downloaded {} to {} ({} bytes, resumed from byte {})",
+                params.url, params.path, total_size, offset
+            )
+        } else {
+            format!(
+                "Successfully downloaded {} to {} ({} bytes)",
+                params.url, params.path, total_size
+            )
+        };
+        if let Some(count) = params.preview_lines.filter(|&count| count > 0) {
+            message.push_str(&download_preview(&params.path, count).await);
+        }
+        Ok(CallToolResult::success(vec![Content::text(message)]))
+    }
+
+    #[tool(
+        description = "Download a URL to a local file and verify its digest, replacing the file only if the digest matches",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = true,
+            open_world_hint = true
+        )
+    )]
+    pub async fn download_verified(
+        &self,
+        Parameters(params): Parameters<DownloadVerifiedParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let expected_hash = params.expected_hash.trim().to_lowercase();
+        if expected_hash.is_empty() || !expected_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "expected_hash must be a hex-encoded digest".to_string(),
+                None,
+            ));
+        }
+
+        // Download next to the target and swap it in only once verified, so a failed or
+        // mismatched download never touches a file already at the path
+        let temp = format!("{}.part-{:08x}", params.path, rand::random::<u32>());
+        let download = DownloadFileParams {
+            url: params.url.clone(),
+            path: temp.clone(),
+            resume: Some(false),
+            preview_lines: None,
+        };
+        let result = match self.download_file(Parameters(download), context).await {
+            Ok(result) => result,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp).await;
+                return Err(e);
+            }
+        };
+        if result.is_error == Some(true) {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Ok(result);
+        }
+
+        let actual_hash = match hash_file_with(std::path::Path::new(&temp), params.algorithm).await
+        {
+            Ok(hash) => hash,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp).await;
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to hash downloaded file: {}", e),
+                    None,
+                ));
+            }
+        };
+        if actual_hash != expected_hash {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "{} mismatch for {}: expected {}, got {}; {} was left unchanged",
+                    params.algorithm.name(),
+                    params.url,
+                    expected_hash,
+                    actual_hash,
+                    params.path
+                ),
+                None,
+            ));
+        }
+        if let Err(e) = tokio::fs::rename(&temp, &params.path).await {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to move verified download to {}: {}", params.path, e),
+                None,
+            ));
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Successfully downloaded {} to {} and verified its {} digest {}",
+            params.url,
+            params.path,
+            params.algorithm.name(),
+            actual_hash
+        ))]))
+    }
+
+    #[tool(
+        description = "Download a URL to a local cache path derived from the URL, reusing a cached copy younger than max_age_secs, and return the local path",
+        annotations(read_only_hint = false, open_world_hint = true)
+    )]
+    pub async fn mirror_url(
+        &self,
+        Parameters(params): Parameters<MirrorUrlParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<Json<MirrorUrlOutput>, McpError> {
+        let path = mirror_path(&self.config.cache_dir, &params.url);
+        if let Ok(metadata) = tokio::fs::metadata(&path).await {
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .unwrap_or_default();
+            let age_secs = age.as_secs();
+            if params
+                .max_age_secs
+                .is_none_or(|max_age| age < Duration::from_secs(max_age))
+            {
+                self.fetch_stats.record_cache_hit();
+                return Ok(Json(MirrorUrlOutput {
+                    path: path.display().to_string(),
+                    cached: true,
+                    age_secs,
+                    size: metadata.len(),
+                }));
+            }
+        }
+
+        if let Err(e) = tokio::fs::create_dir_all(&self.config.cache_dir).await {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to create cache directory: {}", e),
+                None,
+            ));
+        }
+        // Download next to the copy and swap it in, so a failed download keeps the old one
+        let temp = path.with_extension(format!("{:08x}.part", rand::random::<u32>()));
+        let download = DownloadFileParams {
+            url: params.url.clone(),
+            path: temp.display().to_string(),
+            resume: Some(false),
+            preview_lines: None,
+        };
+        let failure = match self.download_file(Parameters(download), context).await {
+            Ok(result) if result.is_error != Some(true) => None,
+            Ok(result) => Some(
+                result
+                    .content
+                    .first()
+                    .and_then(|content| content.as_text())
+                    .map(|text| text.text.clone())
+                    .unwrap_or_else(|| "Download failed".to_string()),
+            ),
+            Err(e) => Some(e.message.to_string()),
+        };
+        if let Some(message) = failure {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                message,
+                None,
+            ));
+        }
+        if let Err(e) = tokio::fs::rename(&temp, &path).await {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to store cached copy: {}", e),
+                None,
+            ));
+        }
+
+        let size = tokio::fs::metadata(&path)
+            .await
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+        Ok(Json(MirrorUrlOutput {
+            path: path.display().to_string(),
+            cached: false,
+            age_secs: 0,
+            size,
+        }))
+    }
+
+    #[tool(
+        description = "Check whether a local file is identical to the body of a URL by comparing their digests, reporting both sizes and digests",
+        annotations(read_only_hint = true, open_world_hint = true),
+        output_schema = cached_schema_for_type::<RemoteComparison>()
+    )]
+    pub async fn compare_with_remote(
+        &self,
+        Parameters(params): Parameters<CompareWithRemoteParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = std::path::Path::new(&params.path);
+        let local = async {
+            let size = tokio::fs::metadata(path).await?.len();
+            let digest = hash_file_with(path, params.algorithm).await?;
+            Ok::<_, std::io::Error>((size, digest))
+        };
+        let (local_size, local_digest) = match local.await {
+            Ok(local) => local,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to hash file: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let _host_permit = self.host_limiter.acquire(&params.url).await;
+        self.fetch_stats.record_request(&params.url);
+        let remote = async {
+            let response = self
+                .default_headers
+                .apply(self.http_client.get(&params.url), None)
+                .send()
+                .await?;
+            let status = response.status();
+            if !status.is_success() {
+                return Ok(Err(status));
+            }
+            let digest = match params.algorithm {
+                HashAlgorithm::Sha256 => digest_response::<sha2::Sha256>(response).await?,
+                HashAlgorithm::Sha384 => digest_response::<sha2::Sha384>(response).await?,
+                HashAlgorithm::Sha512 => digest_response::<sha2::Sha512>(response).await?,
+            };
+            Ok::<_, reqwest::Error>(Ok(digest))
+        };
+        let remote = tokio::select! {
+            remote = remote => remote,
+            _ = context.ct.cancelled() => return Ok(cancelled_result("Comparison")),
+        };
+        let (remote_digest, remote_size) = match remote {
+            Ok(Ok(remote)) => remote,
+            Ok(Err(status)) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Fetching {} failed with status {}", params.url, status),
+                    None,
+                ));
+            }
+            Err(e) => {
+                self.fetch_stats.record_error();
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to fetch URL: {}", e),
+                    None,
+                ));
+            }
+        };
+        self.fetch_stats.record_bytes(remote_size);
+
+        Json(RemoteComparison {
+            identical: local_digest == remote_digest,
+            algorithm: params.algorithm.name().to_string(),
+            local_size,
+            local_digest,
+            remote_size,
+            remote_digest,
+        })
+        .into_call_tool_result()
+    }
+
+    #[tool(
+        description = "Set headers sent with every later request of the fetch tools, such as an API key; headers given to a single fetch take precedence",
+        annotations(read_only_hint = false, destructive_hint = false)
+    )]
+    pub async fn set_default_headers(
+        &self,
+        Parameters(params): Parameters<SetDefaultHeadersParams>,
+    ) -> Result<CallToolResult, McpError> {
+        for (name, value) in &params.headers {
+            if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err()
+                || reqwest::header::HeaderValue::from_str(value).is_err()
+            {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid header {}", name),
+                    None,
+                ));
+            }
+        }
+        // Values are left out, they are often credentials
+        let names = self
+            .default_headers
+            .set(params.headers, params.replace.unwrap_or(false));
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Default headers: {}",
+            names.join(", ")
+        ))]))
+    }
+
+    #[tool(
+        description = "Remove some or all of the default headers installed with set_default_headers",
+        annotations(read_only_hint = false, destructive_hint = false)
+    )]
+    pub async fn clear_default_headers(
+        &self,
+        Parameters(params): Parameters<ClearDefaultHeadersParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (removed, left) = self.default_headers.remove(params.names.as_deref());
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Removed {} default headers, {} left",
+            removed, left
+        ))]))
+    }
+
+    #[tool(
+        description = "Check whether a URL is well-formed before fetching it, and report its components, whether its scheme is http or https and whether its host is internal",
+        annotations(read_only_hint = true)
+    )]
+    pub async fn validate_url(
+        &self,
+        Parameters(params): Parameters<ValidateUrlParams>,
+    ) -> Result<Json<UrlValidation>, McpError> {
+        let url = match reqwest::Url::parse(params.url.trim()) {
+            Ok(url) => url,
+            Err(e) => {
+                return Ok(Json(UrlValidation {
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                }));
+            }
+        };
+        let host = url.host();
+        Ok(Json(UrlValidation {
+            valid: true,
+            error: None,
+            normalized: Some(url.to_string()),
+            scheme: Some(url.scheme().to_string()),
+            scheme_allowed: NETWORK_SCHEMES.contains(&url.scheme()),
+            host: url.host_str().map(str::to_string),
+            host_kind: host.as_ref().map(|host| {
+                match host {
+                    url::Host::Domain(_) => "domain",
+                    url::Host::Ipv4(_) => "ipv4",
+                    url::Host::Ipv6(_) => "ipv6",
+                }
+                .to_string()
+            }),
+            internal_host: host.as_ref().is_some_and(is_internal_host),
+            port: url.port_or_known_default(),
+            path: Some(url.path().to_string()),
+            query: url.query().map(str::to_string),
+            fragment: url.fragment().map(str::to_string),
+        }))
+    }
+
+    #[tool(
+        description = "Send a GraphQL query or mutation and return the data and errors of the response; GraphQL errors are reported as a tool error carrying the response, HTTP failures as a request error",
+        annotations(read_only_hint = false, open_world_hint = true)
+    )]
+    pub async fn graphql(
+        &self,
+        Parameters(params): Parameters<GraphqlParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut envelope = serde_json::Map::new();
+        envelope.insert("query".to_string(), params.query.into());
+        if let Some(variables) = params.variables {
+            envelope.insert("variables".to_string(), variables.into());
+        }
+        if let Some(operation_name) = params.operation_name {
+            envelope.insert("operationName".to_string(), operation_name.into());
+        }
+        let mut headers = params.headers.unwrap_or_default();
+        if !headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case("accept"))
+        {
+            headers.insert(
+                "Accept".to_string(),
+                "application/graphql-response+json, application/json".to_string(),
+            );
+        }
+
+        let response = self
+            .send_fetch(FetchParams {
+                url: params.endpoint,
+                method: HttpMethod::Post,
+                content_type: Some(ContentType::Json),
+                headers: Some(headers),
+                body: Some(envelope.into()),
+                ..Default::default()
+            })
+            .await?;
+
+        // Servers may answer errors with a 4xx status, so look for a GraphQL response first
+        let body = response.body.as_str().unwrap_or_default();
+        let graphql_response = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .filter(|value| value.get("data").is_some() || value.get("errors").is_some());
+        let Some(graphql_response) = graphql_response else {
+            let status = format!("{} {}", response.status, response.status_text);
+            let message = if (200..300).contains(&response.status) {
+                format!("Endpoint answered {} without a GraphQL response", status)
+            } else {
+                format!("GraphQL request failed with HTTP {}", status)
+            };
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("{}: {}", message, truncate_body(body)),
+                None,
+            ));
+        };
+
+        let has_errors = graphql_response
+            .get("errors")
+            .and_then(|errors| errors.as_array())
+            .is_some_and(|errors| !errors.is_empty());
+        let content = vec![Content::text(
+            serde_json::to_string_pretty(&graphql_response)
+                .unwrap_or_else(|_| graphql_response.to_string()),
+        )];
+        if has_errors {
+            Ok(CallToolResult::error(content))
+        } else {
+            Ok(CallToolResult::success(content))
+        }
+    }
+
+    #[tool(
+        description = "Search the web through the configured search API, returning the title, URL and snippet of each result",
+        annotations(read_only_hint = true, open_world_hint = true)
+    )]
+    pub async fn web_search(
+        &self,
+        Parameters(params): Parameters<WebSearchParams>,
+    ) -> Result<Json<WebSearchOutput>, McpError> {
+        let Some(endpoint) = &self.config.search_endpoint else {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_REQUEST,
+                "web_search is not configured, set DIVE_MCP_SEARCH_ENDPOINT to a search API URL"
+                    .to_string(),
+                None,
+            ));
+        };
+        let url = endpoint
+            .replace("{query}", &encode_query_value(&params.query))
+            .replace("{count}", &params.count.unwrap_or(10).to_string());
+        let mut headers = HashMap::new();
+        if let Some(api_key) = &self.config.search_api_key {
+            let header = &self.config.search_api_key_header;
+            let value = if header.eq_ignore_ascii_case("authorization") {
+                format!("Bearer {}", api_key)
+            } else {
+                api_key.clone()
+            };
+            headers.insert(header.clone(), value);
+        }
+
+        let response = self
+            .send_fetch(FetchParams {
+                url,
+                headers: Some(headers),
+                accept: Some("application/json".to_string()),
+                parse_json: Some(true),
+                ..Default::default()
+            })
+            .await?;
+        if !(200..300).contains(&response.status) {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Search API answered {} {}: {}",
+                    response.status,
+                    response.status_text,
+                    truncate_body(&response.body.to_string())
+                ),
+                None,
+            ));
+        }
+        let Some(items) = select_json_path(&response.body, &self.config.search_results_path)
+            .and_then(|items| items.as_array())
+        else {
+            return Err(McpError::new(
                 rmcp::model::ErrorCode::INTERNAL_ERROR,
-                format!("Failed to send request: {}", e),
+                format!(
+                    "Search response has no result array at {}",
+                    self.config.search_results_path
+                ),
                 None,
-            )),
+            ));
+        };
+
+        let mut results: Vec<SearchResult> = items
+            .iter()
+            .map(|item| SearchResult::from_item(item, &self.config.search_fields))
+            .collect();
+        if let Some(count) = params.count {
+            results.truncate(count);
+        }
+        Ok(Json(WebSearchOutput {
+            query: params.query,
+            results,
+        }))
+    }
+
+    #[tool(
+        description = "Poll a URL until it answers with a success or expected status, or a timeout elapses, and report how long it took",
+        annotations(read_only_hint = true, open_world_hint = true)
+    )]
+    pub async fn wait_for_url(
+        &self,
+        Parameters(params): Parameters<WaitForUrlParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let timeout = params.timeout_ms.unwrap_or(30_000);
+        let interval = Duration::from_millis(params.interval_ms.unwrap_or(1_000).max(10));
+        let started = tokio::time::Instant::now();
+        let deadline = started + Duration::from_millis(timeout);
+
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            // Each attempt gets at most the time left, so a hanging server cannot overrun
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            let request = self
+                .default_headers
+                .apply(self.http_client.get(&params.url), None)
+                .timeout(remaining.max(Duration::from_millis(1)))
+                .send();
+            let outcome = tokio::select! {
+                response = request => response,
+                _ = context.ct.cancelled() => return Ok(cancelled_result("Waiting for the URL")),
+            };
+            let last = match outcome {
+                Ok(response) => {
+                    let status = response.status();
+                    let ready = match params.expected_status {
+                        Some(expected) => status.as_u16() == expected,
+                        None => status.is_success(),
+                    };
+                    if ready {
+                        return Ok(CallToolResult::success(vec![Content::text(format!(
+                            "{} answered {} after {} ms and {} attempts",
+                            params.url,
+                            status.as_u16(),
+                            started.elapsed().as_millis(),
+                            attempts
+                        ))]));
+                    }
+                    format!("status {}", status.as_u16())
+                }
+                Err(e) => e.to_string(),
+            };
+
+            if tokio::time::Instant::now() + interval >= deadline {
+                return Ok(CallToolResult::error(vec![Content::text(format!(
+                    "{} was not ready within {} ms after {} attempts, last result: {}",
+                    params.url, timeout, attempts, last
+                ))]));
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = context.ct.cancelled() => return Ok(cancelled_result("Waiting for the URL")),
+            }
         }
     }
+
+    #[tool(
+        description = "Report the Content-Type and Content-Length of a URL without downloading its body, using HEAD or else a one-byte ranged GET",
+        annotations(read_only_hint = true, open_world_hint = true),
+        output_schema = cached_schema_for_type::<RemoteContentType>()
+    )]
+    pub async fn remote_content_type(
+        &self,
+        Parameters(params): Parameters<RemoteContentTypeParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let _host_permit = tokio::select! {
+            permit = self.host_limiter.acquire(&params.url) => permit,
+            _ = context.ct.cancelled() => return Ok(cancelled_result("Request")),
+        };
+
+        self.fetch_stats.record_request(&params.url);
+        let head = tokio::select! {
+            response = self
+                .default_headers
+                .apply(self.http_client.head(&params.url), None)
+                .send() => response,
+            _ = context.ct.cancelled() => return Ok(cancelled_result("Request")),
+        };
+        let (response, method) = match head {
+            // Some servers reject or mishandle HEAD, a ranged GET reads just as few bytes
+            Ok(response) if response.status().is_success() => (response, "HEAD"),
+            _ => {
+                self.fetch_stats.record_request(&params.url);
+                let get = replace_header(
+                    self.default_headers
+                        .apply(self.http_client.get(&params.url), None),
+                    reqwest::header::RANGE,
+                    "bytes=0-0",
+                )
+                .send();
+                let response = tokio::select! {
+                    response = get => response,
+                    _ = context.ct.cancelled() => return Ok(cancelled_result("Request")),
+                };
+                match response {
+                    Ok(response) => (response, "GET"),
+                    Err(e) => {
+                        self.fetch_stats.record_error();
+                        return Err(McpError::new(
+                            rmcp::model::ErrorCode::INTERNAL_ERROR,
+                            format!("Failed to fetch URL: {}", e),
+                            None,
+                        ));
+                    }
+                }
+            }
+        };
+
+        Json(RemoteContentType {
+            final_url: response.url().to_string(),
+            status: response.status().as_u16(),
+            method: method.to_string(),
+            content_type: response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+            content_length: full_content_length(&response),
+        })
+        .into_call_tool_result()
+    }
 }
 
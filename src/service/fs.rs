@@ -1,20 +1,46 @@
 use crate::service::DiveDefaultService;
+use crate::service::data::{ConfigFormat, parse_config};
+use crate::service::text::{LineEnding, convert_line_endings, count_lines, head_and_tail_lines};
 use rmcp::{
     ErrorData as McpError,
-    handler::server::wrapper::Parameters,
+    handler::server::wrapper::{Json, Parameters},
     model::{CallToolResult, Content},
     tool, tool_router,
 };
 
 use base64::{engine::general_purpose, Engine as _};
-use serde::Deserialize;
+use futures::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
 use tokio::fs;
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
 #[derive(Deserialize, schemars::JsonSchema)]
 struct ReadFileParams {
     /// The path to the file to read
     path: String,
+    /// Summarize files larger than this many bytes as their first and last lines instead of
+    /// returning them whole (defaults to the service setting)
+    #[serde(default)]
+    max_bytes: Option<u64>,
+    /// Number of lines a summary keeps from the start and from the end (defaults to the
+    /// service setting)
+    #[serde(default)]
+    summary_lines: Option<usize>,
+    /// Decompress a gzip, bzip2 or zstd file, detected from its first bytes or extension, and
+    /// return the decompressed content (default false)
+    #[serde(default)]
+    decompress: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ReadMultipleFilesParams {
+    /// The paths of the files to read
+    paths: Vec<String>,
+    /// Maximum number of files read at once (defaults to the service setting)
+    #[serde(default)]
+    concurrency: Option<usize>,
 }
 
 #[derive(Deserialize, schemars::JsonSchema)]
@@ -23,12 +49,30 @@ struct WriteFileParams {
     path: String,
     /// The content to write to the file
     content: String,
+    /// Create missing parent directories before writing (default false)
+    #[serde(default)]
+    create_parents: Option<bool>,
+    /// Append a unified diff between the previous and the new content to the result
+    /// (default false)
+    #[serde(default)]
+    return_diff: Option<bool>,
+    /// Convert every line ending of the content to lf or crlf before writing (default the
+    /// content is written as given)
+    #[serde(default)]
+    line_ending: Option<LineEnding>,
+    /// Start the file with a UTF-8 byte order mark, as some Windows tools expect of CSV files
+    /// (default false)
+    #[serde(default)]
+    bom: Option<bool>,
 }
 
 #[derive(Deserialize, schemars::JsonSchema)]
 struct ListDirectoryParams {
     /// The path to the directory to list
     path: String,
+    /// Number of entries to skip, for continuing a truncated listing (default 0)
+    #[serde(default)]
+    offset: Option<usize>,
 }
 
 #[derive(Deserialize, schemars::JsonSchema)]
@@ -43,8 +87,930 @@ struct DeleteFileParams {
     path: String,
 }
 
+#[derive(Deserialize, schemars::JsonSchema)]
+struct MoveFileParams {
+    /// The path of the file to move
+    source: String,
+    /// The path to move the file to
+    destination: String,
+    /// Create missing parent directories of the destination (default false)
+    #[serde(default)]
+    create_parents: Option<bool>,
+    /// When the destination exists, append " (1)", " (2)", ... before its extension until the
+    /// name is free instead of replacing it (default false)
+    #[serde(default)]
+    unique: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ArchiveFileParams {
+    /// The path of the file to archive
+    path: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct CopyFileParams {
+    /// The path of the file to copy
+    source: String,
+    /// The path to copy the file to
+    destination: String,
+    /// Create missing parent directories of the destination (default false)
+    #[serde(default)]
+    create_parents: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct PreviewWriteParams {
+    /// The path to the file that would be written
+    path: String,
+    /// The proposed new content of the file
+    content: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct StatFileParams {
+    /// The path to the file or directory to inspect
+    path: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct StatFileOutput {
+    /// The inspected path
+    path: String,
+    /// "file", "directory" or "symlink"
+    #[serde(rename = "type")]
+    file_type: String,
+    /// Size in bytes
+    size: u64,
+    /// Last modification time as RFC3339
+    modified: Option<String>,
+    /// Last access time as RFC3339
+    accessed: Option<String>,
+    /// Creation time as RFC3339, where the platform records it
+    created: Option<String>,
+    readonly: bool,
+    /// Target of a symlink
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct SetMtimeParams {
+    /// The path to the file to update
+    path: String,
+    /// The new modification time as an RFC3339 timestamp, or "now"
+    mtime: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct PeekFileParams {
+    /// The path to the file to peek at
+    path: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum SplitMode {
+    Bytes,
+    Lines,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct SplitFileParams {
+    /// The path to the file to split
+    path: String,
+    /// Size of each chunk, in bytes or lines depending on mode
+    chunk_size: u64,
+    /// Split by bytes or by lines (default bytes)
+    #[serde(default)]
+    mode: Option<SplitMode>,
+    /// The directory to write the chunks to, created if missing; chunks are named after the
+    /// file with a numbered suffix (.001, .002, ...), with more digits past 999 chunks
+    output_dir: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct JoinFilesParams {
+    /// The paths of the files to concatenate, in order
+    paths: Vec<String>,
+    /// The path to write the joined file to, replacing it if it exists
+    output: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct PrependFileParams {
+    /// The path to the text file to prepend to
+    path: String,
+    /// The content to insert at the beginning of the file
+    content: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct RealpathParams {
+    /// The path to resolve
+    path: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct SymlinkHop {
+    /// The symlink that was followed
+    link: String,
+    /// Where the symlink points, as stored in it
+    target: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct RealpathOutput {
+    /// The requested path
+    path: String,
+    /// Every symlink followed while resolving, in order
+    hops: Vec<SymlinkHop>,
+    /// The absolute path with every symlink resolved
+    resolved: String,
+    /// Whether the resolved path exists; a missing part is kept as given
+    exists: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct DedupPathsParams {
+    /// The paths to canonicalize and deduplicate
+    paths: Vec<String>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct DedupPathsOutput {
+    /// The distinct canonical paths, in the order they first appeared
+    unique: Vec<String>,
+    /// Canonical paths given more than once, with the paths that led to them
+    duplicates: BTreeMap<String, Vec<String>>,
+    /// Paths that could not be resolved, such as symlink cycles, with the reason
+    errors: BTreeMap<String, String>,
+}
+
+/// Symlinks followed while resolving a path before giving up, like the kernel's limit
+const MAX_SYMLINK_HOPS: usize = 40;
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct CreateHardlinkParams {
+    /// The existing file to link to
+    target: String,
+    /// The new path that will refer to the same file
+    link_path: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ClearFileParams {
+    /// The path to the file to empty
+    path: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct FilesystemStatsParams {
+    /// A path on the filesystem to inspect; it does not have to exist yet
+    path: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct FilesystemStatsOutput {
+    /// The inspected path
+    path: String,
+    /// Size of the filesystem in bytes
+    total: u64,
+    /// Bytes in use
+    used: u64,
+    /// Bytes available to this process, which can be less than the free bytes
+    available: u64,
+    /// Bytes not in use
+    free: u64,
+    /// Share of the filesystem in use, from 0 to 100
+    used_percent: f64,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ListDrivesParams {
+    /// Also list virtual filesystems such as /proc and /sys on Linux (default false)
+    #[serde(default)]
+    include_virtual: Option<bool>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct DriveEntry {
+    /// Root directory of the drive or mount point
+    path: String,
+    /// Filesystem type, such as ext4
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filesystem: Option<String>,
+    /// Device or source that is mounted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device: Option<String>,
+    /// Size of the filesystem in bytes, when it could be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<u64>,
+    /// Bytes available to this process, when it could be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    available: Option<u64>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct ListDrivesOutput {
+    /// Drive letters on Windows, or mount points elsewhere
+    drives: Vec<DriveEntry>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct CanWriteParams {
+    /// The path of the file to check
+    path: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct CanWriteOutput {
+    /// The checked path
+    path: String,
+    /// Whether writing the file is expected to succeed
+    writable: bool,
+    /// Why the file cannot be written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct HexdumpParams {
+    /// The path to the file to dump
+    path: String,
+    /// Byte offset to start at (default 0)
+    #[serde(default)]
+    offset: Option<u64>,
+    /// Number of bytes to dump (default 256, at most 65536)
+    #[serde(default)]
+    length: Option<usize>,
+}
+
+/// Largest range `hexdump` dumps in one call
+const MAX_HEXDUMP_BYTES: usize = 64 * 1024;
+
+/// Format bytes like `hexdump -C`: offset, sixteen hex bytes and their printable ASCII
+fn format_hexdump(bytes: &[u8], offset: u64) -> String {
+    let mut output = String::new();
+    for (index, row) in bytes.chunks(16).enumerate() {
+        output.push_str(&format!("{:08x} ", offset + index as u64 * 16));
+        for column in 0..16 {
+            if column == 8 {
+                output.push(' ');
+            }
+            match row.get(column) {
+                Some(byte) => output.push_str(&format!(" {:02x}", byte)),
+                None => output.push_str("   "),
+            }
+        }
+        output.push_str("  |");
+        output.extend(row.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        }));
+        output.push_str("|\n");
+    }
+    output
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ValidateFileParams {
+    /// The path to the file to validate
+    path: String,
+    /// Largest acceptable size in bytes
+    #[serde(default)]
+    max_size: Option<u64>,
+    /// Acceptable extensions, case-insensitive, e.g. ["csv", "tsv"]
+    #[serde(default)]
+    extensions: Option<Vec<String>>,
+    /// Expected MIME type as detected from the content, e.g. "image/png" or "image/*"
+    #[serde(default)]
+    mime: Option<String>,
+    /// Require the content to parse as this format (json, yaml or toml)
+    #[serde(default)]
+    format: Option<ConfigFormat>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct ValidateFileOutput {
+    /// The validated path
+    path: String,
+    /// Whether every constraint passed
+    valid: bool,
+    /// The constraints that failed, with reasons
+    failures: Vec<String>,
+    /// Size of the file in bytes, when it could be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    /// MIME type detected from the content, when it could be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mime: Option<String>,
+}
+
+/// Number of bytes `peek_file` reads from the start of a file
+const PEEK_BYTES: usize = 4096;
+
+/// Leading magic bytes of common binary formats and their MIME types
+const MAGIC_MIME_TYPES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"BZh", "application/x-bzip2"),
+    (b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (b"\x7fELF", "application/x-elf"),
+    (b"\0asm", "application/wasm"),
+];
+
+/// Detect the MIME type of file content from its leading bytes
+pub fn detect_mime(head: &[u8]) -> &'static str {
+    if let Some((_, mime)) = MAGIC_MIME_TYPES
+        .iter()
+        .find(|(magic, _)| head.starts_with(magic))
+    {
+        return mime;
+    }
+    if head.contains(&0) {
+        "application/octet-stream"
+    } else {
+        "text/plain"
+    }
+}
+
+/// Format a filesystem timestamp as RFC3339
+pub fn format_system_time(time: std::time::SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()
+}
+
+/// Render a unified diff between the old and new contents of a file
+fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(path, path)
+        .to_string()
+}
+
+/// Create the missing parent directories of a path
+async fn create_parent_dirs(path: &str) -> Result<(), McpError> {
+    if let Some(parent) = std::path::Path::new(path).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent).await.map_err(|e| {
+            McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to create parent directories: {}", e),
+                None,
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// The first of `path`, `name (1).ext`, `name (2).ext`, ... that does not exist yet
+async fn unique_path(path: &str) -> String {
+    let original = std::path::Path::new(path);
+    let stem = original
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = original
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut candidate = original.to_path_buf();
+    let mut number = 0;
+    while fs::symlink_metadata(&candidate).await.is_ok() {
+        number += 1;
+        candidate = original.with_file_name(format!("{} ({}){}", stem, number, extension));
+    }
+    candidate.to_string_lossy().into_owned()
+}
+
+/// Replace the content of a file while holding an advisory exclusive lock on it, waiting up to
+/// `timeout` for other processes to release their lock
+async fn write_file_locked(path: &str, content: &[u8], timeout: Duration) -> std::io::Result<()> {
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .await?
+        .into_std()
+        .await;
+
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => break,
+            Err(std::fs::TryLockError::WouldBlock) if started.elapsed() < timeout => {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            Err(std::fs::TryLockError::WouldBlock) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::WouldBlock,
+                    "file is locked by another process",
+                ));
+            }
+            Err(std::fs::TryLockError::Error(e)) => return Err(e),
+        }
+    }
+
+    // The lock is released when the file is closed
+    let mut file = fs::File::from_std(file);
+    file.set_len(0).await?;
+    file.write_all(content).await?;
+    file.flush().await
+}
+
+/// Replace a file with `content` by writing it to a temporary file next to it and renaming
+/// that over the original, so the file is never left partially written. Returns the new size.
+pub async fn replace_file_atomically(
+    path: &str,
+    mut content: impl tokio::io::AsyncRead + Unpin,
+) -> std::io::Result<u64> {
+    let target = std::path::Path::new(path);
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let temp = target.with_file_name(format!(".{}.{:08x}.tmp", name, rand::random::<u32>()));
+
+    let replace = async {
+        let mut file = fs::File::create(&temp).await?;
+        let size = tokio::io::copy(&mut content, &mut file).await?;
+        file.sync_all().await?;
+        if let Ok(metadata) = fs::metadata(target).await {
+            fs::set_permissions(&temp, metadata.permissions()).await?;
+        }
+        fs::rename(&temp, target).await?;
+        Ok(size)
+    };
+    let result = replace.await;
+    if result.is_err() {
+        let _ = fs::remove_file(&temp).await;
+    }
+    result
+}
+
+/// Resolve every symlink in a path one component at a time, recording each hop. Fails on a
+/// symlink cycle, naming the links in the loop.
+async fn resolve_symlinks(path: &str) -> Result<RealpathOutput, McpError> {
+    use std::path::{Component, PathBuf};
+
+    let absolute = match std::path::absolute(path) {
+        Ok(absolute) => absolute,
+        Err(e) => {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!("Invalid path {}: {}", path, e),
+                None,
+            ));
+        }
+    };
+    let mut pending: Vec<PathBuf> = absolute
+        .components()
+        .rev()
+        .map(|component| PathBuf::from(component.as_os_str()))
+        .collect();
+    let mut resolved = PathBuf::new();
+    let mut hops = Vec::new();
+    // Pairs of a followed link and what was left to resolve; seeing one again is a cycle
+    let mut states: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+    let mut exists = true;
+
+    while let Some(part) = pending.pop() {
+        let candidate = match part.components().next() {
+            Some(Component::CurDir) => continue,
+            Some(Component::ParentDir) => {
+                resolved.pop();
+                continue;
+            }
+            Some(Component::Normal(_)) => resolved.join(&part),
+            _ => {
+                resolved.push(&part);
+                continue;
+            }
+        };
+        let metadata = match fs::symlink_metadata(&candidate).await {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                exists = false;
+                resolved = candidate;
+                continue;
+            }
+        };
+        if !metadata.is_symlink() {
+            resolved = candidate;
+            continue;
+        }
+
+        let state = (candidate.clone(), pending.clone());
+        if let Some(start) = states.iter().position(|seen| *seen == state) {
+            let links: Vec<String> = states[start..]
+                .iter()
+                .map(|(link, _)| link.display().to_string())
+                .collect();
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Symlink cycle resolving {}: {} -> {}",
+                    path,
+                    links.join(" -> "),
+                    candidate.display()
+                ),
+                None,
+            ));
+        }
+        if hops.len() >= MAX_SYMLINK_HOPS {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Gave up resolving {} after {} symlinks",
+                    path, MAX_SYMLINK_HOPS
+                ),
+                None,
+            ));
+        }
+        states.push(state);
+
+        let target = match fs::read_link(&candidate).await {
+            Ok(target) => target,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read symlink {}: {}", candidate.display(), e),
+                    None,
+                ));
+            }
+        };
+        hops.push(SymlinkHop {
+            link: candidate.display().to_string(),
+            target: target.display().to_string(),
+        });
+        // A relative target is resolved from the link's directory, an absolute one from the root
+        if target.is_absolute() {
+            resolved = PathBuf::new();
+        }
+        pending.extend(
+            target
+                .components()
+                .rev()
+                .map(|component| PathBuf::from(component.as_os_str())),
+        );
+    }
+
+    Ok(RealpathOutput {
+        path: path.to_string(),
+        hops,
+        resolved: resolved.display().to_string(),
+        exists,
+    })
+}
+
+/// Space statistics of the filesystem holding a path, looking at its closest existing ancestor
+/// when the path does not exist yet
+fn filesystem_stats_of(path: &str) -> std::io::Result<fs4::FsStats> {
+    let mut existing = std::path::absolute(path)?;
+    while !existing.exists() && existing.pop() {}
+    fs4::statvfs(&existing)
+}
+
+impl DiveDefaultService {
+    /// Refuse writing `bytes` bytes to `path` when that would leave less than the configured
+    /// minimum free on its filesystem. Tools writing bulk data call this before they start.
+    pub fn ensure_free_space(&self, path: &str, bytes: u64) -> Result<(), McpError> {
+        if let Some(min_free_bytes) = self.config.min_free_bytes
+            && let Ok(stats) = filesystem_stats_of(path)
+            && stats.available_space() < min_free_bytes.saturating_add(bytes)
+        {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Refusing to write {}: only {} bytes are available and at least {} must stay free",
+                    path,
+                    stats.available_space(),
+                    min_free_bytes
+                ),
+                None,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Kernel filesystems of /proc/mounts that hold no user files
+const VIRTUAL_FILESYSTEMS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "securityfs",
+    "sysfs",
+    "tracefs",
+];
+
+/// Undo the octal escapes /proc/mounts uses for spaces and other special characters
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && i + 3 < bytes.len()
+            && let Some(byte) = std::str::from_utf8(&bytes[i + 1..i + 4])
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 8).ok())
+        {
+            decoded.push(byte);
+            i += 4;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The drive letters or mount points of this machine, without space statistics
+async fn list_mount_points(include_virtual: bool) -> Vec<DriveEntry> {
+    let drive = |path: String, filesystem: Option<String>, device: Option<String>| DriveEntry {
+        path,
+        filesystem,
+        device,
+        total: None,
+        available: None,
+    };
+    if cfg!(windows) {
+        return ('A'..='Z')
+            .map(|letter| format!("{}:\\", letter))
+            .filter(|root| std::path::Path::new(root).exists())
+            .map(|root| drive(root, None, None))
+            .collect();
+    }
+    let Ok(mounts) = fs::read_to_string("/proc/mounts").await else {
+        return vec![drive("/".to_string(), None, None)];
+    };
+    let mut drives: Vec<DriveEntry> = Vec::new();
+    for line in mounts.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(device), Some(path), Some(filesystem)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if !include_virtual && VIRTUAL_FILESYSTEMS.contains(&filesystem) {
+            continue;
+        }
+        let path = unescape_mount_field(path);
+        // A later mount over the same point hides the earlier one
+        drives.retain(|existing| existing.path != path);
+        drives.push(drive(
+            path,
+            Some(filesystem.to_string()),
+            Some(unescape_mount_field(device)),
+        ));
+    }
+    drives
+}
+
+/// Whether an error means another process has the file open or locked, such as a Windows
+/// sharing violation
+fn is_file_in_use(error: &std::io::Error) -> bool {
+    // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+    if cfg!(windows) && matches!(error.raw_os_error(), Some(32 | 33)) {
+        return true;
+    }
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::ResourceBusy | std::io::ErrorKind::ExecutableFileBusy
+    )
+}
+
+/// Number of times an operation on a file in use is retried before giving up
+const IN_USE_RETRIES: u32 = 3;
+
+/// Run a file operation, retrying with backoff while the file is in use, since such locks
+/// are often transient (virus scanners, indexers)
+async fn retry_while_in_use<T, F, Fut>(mut operation: F) -> std::io::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::io::Result<T>>,
+{
+    let mut delay = Duration::from_millis(100);
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Err(e) if is_file_in_use(&e) && attempt < IN_USE_RETRIES => {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Describe a failed file operation, explaining errors caused by the file being in use
+fn file_operation_error(operation: &str, path: &str, error: std::io::Error) -> McpError {
+    let message = if is_file_in_use(&error) {
+        format!(
+            "Failed to {}: {} is in use by another process, close it and try again",
+            operation, path
+        )
+    } else {
+        format!("Failed to {}: {}", operation, error)
+    };
+    McpError::new(rmcp::model::ErrorCode::INTERNAL_ERROR, message, None)
+}
+
+/// The first and last lines of a file too large to return, with its size and line count
+async fn summarize_large_file(path: &str, size: u64, lines: usize) -> Result<String, McpError> {
+    let summary = async {
+        if is_binary_file(path).await? {
+            return Ok(format!(
+                "[Binary file of {} bytes, too large to return]",
+                size
+            ));
+        }
+        let total = count_lines(path).await?;
+        let (head, tail) = head_and_tail_lines(path, lines).await?;
+        let mut summary = format!(
+            "[File truncated: {} bytes, {} lines; showing the first {} and last {} lines]\n",
+            size,
+            total,
+            head.len(),
+            tail.len()
+        );
+        for line in &head {
+            summary.push_str(line);
+            summary.push('\n');
+        }
+        summary.push_str(&format!(
+            "[... {} lines omitted ...]\n",
+            total.saturating_sub(head.len() + tail.len())
+        ));
+        for line in &tail {
+            summary.push_str(line);
+            summary.push('\n');
+        }
+        Ok::<_, std::io::Error>(summary)
+    };
+    summary.await.map_err(|e| {
+        McpError::new(
+            rmcp::model::ErrorCode::INTERNAL_ERROR,
+            format!("Failed to read file: {}", e),
+            None,
+        )
+    })
+}
+
+/// Largest decompressed size `read_file` accepts, guarding against decompression bombs
+const MAX_DECOMPRESSED_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Compression formats `read_file` can decompress
+#[derive(Clone, Copy)]
+enum Compression {
+    Gzip,
+    Bzip2,
+    Zstd,
+}
+
+impl Compression {
+    /// Detect the compression of a file from its magic bytes, falling back to its extension
+    fn detect(path: &str, head: &[u8]) -> Option<Self> {
+        if head.starts_with(&[0x1f, 0x8b]) {
+            return Some(Self::Gzip);
+        }
+        if head.starts_with(b"BZh") {
+            return Some(Self::Bzip2);
+        }
+        if head.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            return Some(Self::Zstd);
+        }
+        let extension = std::path::Path::new(path).extension()?.to_str()?;
+        match extension.to_lowercase().as_str() {
+            "gz" | "gzip" => Some(Self::Gzip),
+            "bz2" => Some(Self::Bzip2),
+            "zst" | "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Bzip2 => "bzip2",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Decompress data, failing when the result would exceed `limit` bytes
+    fn decompress(self, data: &[u8], limit: u64) -> std::io::Result<Vec<u8>> {
+        use std::io::Read;
+
+        let reader: Box<dyn Read + '_> = match self {
+            Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(data)),
+            Self::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(data)),
+            Self::Zstd => Box::new(zstd::stream::read::Decoder::new(data)?),
+        };
+        let mut decompressed = Vec::new();
+        reader.take(limit + 1).read_to_end(&mut decompressed)?;
+        if decompressed.len() as u64 > limit {
+            return Err(std::io::Error::other(format!(
+                "decompressed content is larger than {} bytes",
+                limit
+            )));
+        }
+        Ok(decompressed)
+    }
+}
+
+/// Read a compressed file and describe its decompressed content, encoding it as base64 when
+/// it is not text
+async fn read_compressed_file(path: &str) -> Result<String, McpError> {
+    let data = match fs::read(path).await {
+        Ok(data) => data,
+        Err(e) => {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to read file: {}", e),
+                None,
+            ));
+        }
+    };
+    let Some(compression) = Compression::detect(path, &data) else {
+        return Err(McpError::new(
+            rmcp::model::ErrorCode::INVALID_PARAMS,
+            format!("{} is not a gzip, bzip2 or zstd file", path),
+            None,
+        ));
+    };
+    let compressed_size = data.len();
+    let decompressed =
+        tokio::task::spawn_blocking(move || compression.decompress(&data, MAX_DECOMPRESSED_BYTES))
+            .await
+            .map_err(std::io::Error::other)
+            .flatten();
+    let decompressed = match decompressed {
+        Ok(decompressed) => decompressed,
+        Err(e) => {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Failed to decompress {} as {}: {}",
+                    path,
+                    compression.name(),
+                    e
+                ),
+                None,
+            ));
+        }
+    };
+
+    let header = format!(
+        "[Decompressed {}: {} bytes -> {} bytes]",
+        compression.name(),
+        compressed_size,
+        decompressed.len()
+    );
+    // Like read_file, content with null bytes or invalid UTF-8 is binary
+    Ok(match String::from_utf8(decompressed) {
+        Ok(text) if !text.contains('\0') => format!("{}\n{}", header, text),
+        Ok(text) => binary_content(&header, text.as_bytes()),
+        Err(e) => binary_content(&header, e.as_bytes()),
+    })
+}
+
+/// Describe binary content as base64 below a header
+fn binary_content(header: &str, bytes: &[u8]) -> String {
+    format!(
+        "{} [Binary content encoded as base64]\n{}",
+        header,
+        general_purpose::STANDARD.encode(bytes)
+    )
+}
+
 /// Check if a file is binary by reading the first 8KB and looking for null bytes
-async fn is_binary_file(path: &str) -> Result<bool, std::io::Error> {
+pub async fn is_binary_file(path: &str) -> Result<bool, std::io::Error> {
     let mut file = fs::File::open(path).await?;
     let mut buffer = vec![0u8; 8192];
     let bytes_read = file.read(&mut buffer).await?;
@@ -55,13 +1021,49 @@ async fn is_binary_file(path: &str) -> Result<bool, std::io::Error> {
 
 #[tool_router(router = tool_router_fs, vis = "pub")]
 impl DiveDefaultService {
-    #[tool(description = "Read file content from the specified path")]
+    #[tool(
+        description = "Read file content from the specified path",
+        annotations(read_only_hint = true)
+    )]
     async fn read_file(
         &self,
         Parameters(params): Parameters<ReadFileParams>,
     ) -> Result<CallToolResult, McpError> {
+        if params.decompress.unwrap_or(false) {
+            let content = read_compressed_file(&params.path).await?;
+            return Ok(CallToolResult::success(vec![Content::text(content)]));
+        }
+        let max_bytes = params.max_bytes.or(self.config.max_read_bytes);
+        let summary_lines = params.summary_lines.unwrap_or(self.config.summary_lines);
+        let content = self
+            .read_file_content(&params.path, max_bytes, summary_lines)
+            .await?;
+        Ok(CallToolResult::success(vec![Content::text(content)]))
+    }
+
+    /// Read a file as text, encoding binary files as base64 and summarizing files larger than
+    /// `max_bytes`
+    async fn read_file_content(
+        &self,
+        path: &str,
+        max_bytes: Option<u64>,
+        summary_lines: usize,
+    ) -> Result<String, McpError> {
+        if !self.config.follow_symlinks
+            && let Ok(target) = fs::read_link(path).await
+        {
+            return Ok(format!("[Symbolic link to {}]", target.display()));
+        }
+
+        if let Some(max_bytes) = max_bytes
+            && let Ok(metadata) = fs::metadata(path).await
+            && metadata.len() > max_bytes
+        {
+            return summarize_large_file(path, metadata.len(), summary_lines).await;
+        }
+
         // Check if file is binary
-        let is_binary = match is_binary_file(&params.path).await {
+        let is_binary = match is_binary_file(path).await {
             Ok(is_bin) => is_bin,
             Err(e) => {
                 return Err(McpError::new(
@@ -74,13 +1076,13 @@ impl DiveDefaultService {
 
         if is_binary {
             // Read binary file and encode as base64
-            match fs::read(&params.path).await {
+            match fs::read(path).await {
                 Ok(bytes) => {
                     let base64_content = general_purpose::STANDARD.encode(&bytes);
-                    Ok(CallToolResult::success(vec![Content::text(format!(
+                    Ok(format!(
                         "[Binary file encoded as base64]\n{}",
                         base64_content
-                    ))]))
+                    ))
                 }
                 Err(e) => Err(McpError::new(
                     rmcp::model::ErrorCode::INTERNAL_ERROR,
@@ -90,8 +1092,8 @@ impl DiveDefaultService {
             }
         } else {
             // Read text file normally
-            match fs::read_to_string(&params.path).await {
-                Ok(content) => Ok(CallToolResult::success(vec![Content::text(content)])),
+            match fs::read_to_string(path).await {
+                Ok(content) => Ok(content),
                 Err(e) => Err(McpError::new(
                     rmcp::model::ErrorCode::INTERNAL_ERROR,
                     format!("Failed to read file: {}", e),
@@ -101,16 +1103,97 @@ impl DiveDefaultService {
         }
     }
 
-    #[tool(description = "Write content to a file at the specified path")]
+    #[tool(
+        description = "Read the contents of several files at once",
+        annotations(read_only_hint = true)
+    )]
+    async fn read_multiple_files(
+        &self,
+        Parameters(params): Parameters<ReadMultipleFilesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let concurrency = self.batch_concurrency(params.concurrency);
+        let sections: Vec<String> = stream::iter(params.paths)
+            .map(|path| async move {
+                let read = self.read_file_content(
+                    &path,
+                    self.config.max_read_bytes,
+                    self.config.summary_lines,
+                );
+                match read.await {
+                    Ok(content) => format!("{}:\n{}", path, content),
+                    Err(e) => format!("{}: Error - {}", path, e.message),
+                }
+            })
+            .buffered(concurrency)
+            .collect()
+            .await;
+
+        Ok(CallToolResult::success(vec![Content::text(
+            sections.join("\n---\n"),
+        )]))
+    }
+
+    #[tool(
+        description = "Write content to a file at the specified path",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
     async fn write_file(
         &self,
         Parameters(params): Parameters<WriteFileParams>,
     ) -> Result<CallToolResult, McpError> {
-        match fs::write(&params.path, &params.content).await {
-            Ok(_) => Ok(CallToolResult::success(vec![Content::text(format!(
-                "Successfully wrote to {}",
-                params.path
-            ))])),
+        let mut content = match params.line_ending {
+            Some(line_ending) => convert_line_endings(&params.content, line_ending).0,
+            None => params.content,
+        };
+        if params.bom.unwrap_or(false) && !content.starts_with('\u{feff}') {
+            content.insert(0, '\u{feff}');
+        }
+
+        self.ensure_free_space(&params.path, content.len() as u64)?;
+
+        if params.create_parents.unwrap_or(false) {
+            create_parent_dirs(&params.path).await?;
+        }
+
+        // A missing file diffs as empty content; a binary or unreadable one is not diffed
+        let previous = if params.return_diff.unwrap_or(false) {
+            match fs::read(&params.path).await {
+                Ok(bytes) => Some(String::from_utf8(bytes).ok()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Some(Some(String::new())),
+                Err(_) => Some(None),
+            }
+        } else {
+            None
+        };
+
+        let written = if self.config.lock_writes {
+            write_file_locked(
+                &params.path,
+                content.as_bytes(),
+                Duration::from_millis(self.config.lock_timeout_ms),
+            )
+            .await
+        } else {
+            fs::write(&params.path, &content).await
+        };
+        match written {
+            Ok(_) => {
+                let mut message = format!("Successfully wrote to {}", params.path);
+                match previous {
+                    Some(Some(previous)) => {
+                        let diff = unified_diff(&params.path, &previous, &content);
+                        if diff.is_empty() {
+                            message.push_str("\n\nNo changes");
+                        } else {
+                            message.push_str("\n\n");
+                            message.push_str(&diff);
+                        }
+                    }
+                    Some(None) => message.push_str("\n\nNo diff: the previous content is not text"),
+                    None => {}
+                }
+                Ok(CallToolResult::success(vec![Content::text(message)]))
+            }
             Err(e) => Err(McpError::new(
                 rmcp::model::ErrorCode::INTERNAL_ERROR,
                 format!("Failed to write file: {}", e),
@@ -119,23 +1202,51 @@ impl DiveDefaultService {
         }
     }
 
-    #[tool(description = "List all files and directories in the specified path")]
+    #[tool(
+        description = "List all files and directories in the specified path",
+        annotations(read_only_hint = true)
+    )]
     async fn list_directory(
         &self,
         Parameters(params): Parameters<ListDirectoryParams>,
     ) -> Result<CallToolResult, McpError> {
         match fs::read_dir(&params.path).await {
             Ok(mut entries) => {
+                let offset = params.offset.unwrap_or(0);
+                let max_entries = self.config.max_list_entries;
                 let mut items = Vec::new();
+                let mut skipped = 0;
+                let mut truncated = false;
                 while let Ok(Some(entry)) = entries.next_entry().await {
-                    if let Ok(file_name) = entry.file_name().into_string() {
-                        let file_type = if entry.path().is_dir() {
-                            "directory"
-                        } else {
-                            "file"
-                        };
-                        items.push(format!("{} ({})", file_name, file_type));
+                    // Names that are not UTF-8 are never listed, so offset does not count them
+                    let Ok(file_name) = entry.file_name().into_string() else {
+                        continue;
+                    };
+                    if skipped < offset {
+                        skipped += 1;
+                        continue;
+                    }
+                    if items.len() >= max_entries {
+                        truncated = true;
+                        break;
                     }
+                    let is_symlink = !self.config.follow_symlinks
+                        && entry.file_type().await.is_ok_and(|t| t.is_symlink());
+                    let file_type = if is_symlink {
+                        "symlink"
+                    } else if entry.path().is_dir() {
+                        "directory"
+                    } else {
+                        "file"
+                    };
+                    items.push(format!("{} ({})", file_name, file_type));
+                }
+                if truncated {
+                    items.push(format!(
+                        "[Listing truncated after {} entries, use offset {} to list more]",
+                        max_entries,
+                        offset + max_entries
+                    ));
                 }
                 Ok(CallToolResult::success(vec![Content::text(
                     items.join("\n"),
@@ -149,7 +1260,10 @@ impl DiveDefaultService {
         }
     }
 
-    #[tool(description = "Create a new directory at the specified path")]
+    #[tool(
+        description = "Create a new directory at the specified path",
+        annotations(read_only_hint = false, destructive_hint = false)
+    )]
     async fn create_directory(
         &self,
         Parameters(params): Parameters<CreateDirectoryParams>,
@@ -167,21 +1281,888 @@ impl DiveDefaultService {
         }
     }
 
-    #[tool(description = "Delete a file at the specified path")]
+    #[tool(
+        description = "Delete a file at the specified path",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
     async fn delete_file(
         &self,
         Parameters(params): Parameters<DeleteFileParams>,
     ) -> Result<CallToolResult, McpError> {
-        match fs::remove_file(&params.path).await {
+        match retry_while_in_use(|| fs::remove_file(&params.path)).await {
             Ok(_) => Ok(CallToolResult::success(vec![Content::text(format!(
                 "Successfully deleted file: {}",
                 params.path
             ))])),
+            Err(e) => Err(file_operation_error("delete file", &params.path, e)),
+        }
+    }
+
+    #[tool(
+        description = "Move or rename a file to the specified destination",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn move_file(
+        &self,
+        Parameters(params): Parameters<MoveFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if params.create_parents.unwrap_or(false) {
+            create_parent_dirs(&params.destination).await?;
+        }
+        let destination = if params.unique.unwrap_or(false) {
+            unique_path(&params.destination).await
+        } else {
+            params.destination
+        };
+
+        match retry_while_in_use(|| fs::rename(&params.source, &destination)).await {
+            Ok(_) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Successfully moved {} to {}",
+                params.source, destination
+            ))])),
+            Err(e) => Err(file_operation_error("move file", &params.source, e)),
+        }
+    }
+
+    #[tool(
+        description = "Move a file into an archive/YYYY/MM/ folder for the current month under the archive root, renaming it on a collision, and return its new path",
+        annotations(read_only_hint = false, destructive_hint = false)
+    )]
+    async fn archive_file(
+        &self,
+        Parameters(params): Parameters<ArchiveFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let source = std::path::Path::new(&params.path);
+        let Some(name) = source.file_name() else {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!("{} does not name a file", params.path),
+                None,
+            ));
+        };
+        match fs::metadata(source).await {
+            Ok(metadata) if metadata.is_file() => {}
+            Ok(_) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("{} is not a file", params.path),
+                    None,
+                ));
+            }
+            Err(e) => return Err(file_operation_error("archive file", &params.path, e)),
+        }
+
+        let root = match &self.config.archive_root {
+            Some(root) => root.clone(),
+            None => source
+                .parent()
+                .map(std::path::Path::to_path_buf)
+                .unwrap_or_default(),
+        };
+        let now = chrono::Local::now();
+        let folder = root
+            .join("archive")
+            .join(now.format("%Y").to_string())
+            .join(now.format("%m").to_string());
+        if let Err(e) = fs::create_dir_all(&folder).await {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to create archive folder: {}", e),
+                None,
+            ));
+        }
+        let destination = unique_path(&folder.join(name).to_string_lossy()).await;
+
+        let moved = match retry_while_in_use(|| fs::rename(&params.path, &destination)).await {
+            // The archive root can be on another filesystem, which rename cannot reach
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                let size = fs::metadata(&params.path).await.map_or(0, |m| m.len());
+                self.ensure_free_space(&destination, size)?;
+                match fs::copy(&params.path, &destination).await {
+                    Ok(_) => fs::remove_file(&params.path).await,
+                    Err(e) => Err(e),
+                }
+            }
+            moved => moved,
+        };
+        match moved {
+            Ok(_) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Archived {} to {}",
+                params.path, destination
+            ))])),
+            Err(e) => Err(file_operation_error("archive file", &params.path, e)),
+        }
+    }
+
+    #[tool(
+        description = "Copy a file to the specified destination",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn copy_file(
+        &self,
+        Parameters(params): Parameters<CopyFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if let Ok(metadata) = fs::metadata(&params.source).await {
+            self.ensure_free_space(&params.destination, metadata.len())?;
+        }
+        if params.create_parents.unwrap_or(false) {
+            create_parent_dirs(&params.destination).await?;
+        }
+
+        match fs::copy(&params.source, &params.destination).await {
+            Ok(bytes) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Successfully copied {} to {} ({} bytes)",
+                params.source, params.destination, bytes
+            ))])),
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to copy file: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Create a hard link, a second path to the same file on the same filesystem",
+        annotations(read_only_hint = false, destructive_hint = false)
+    )]
+    async fn create_hardlink(
+        &self,
+        Parameters(params): Parameters<CreateHardlinkParams>,
+    ) -> Result<CallToolResult, McpError> {
+        match fs::hard_link(&params.target, &params.link_path).await {
+            Ok(_) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Successfully linked {} to {}",
+                params.link_path, params.target
+            ))])),
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Cannot hard link {} to {}: hard links cannot cross filesystems, copy the file instead",
+                    params.link_path, params.target
+                ),
+                None,
+            )),
             Err(e) => Err(McpError::new(
                 rmcp::model::ErrorCode::INTERNAL_ERROR,
-                format!("Failed to delete file: {}", e),
+                format!("Failed to create hard link: {}", e),
                 None,
             )),
         }
     }
+
+    #[tool(
+        description = "Show the unified diff a write_file call with this content would apply, without writing",
+        annotations(read_only_hint = true)
+    )]
+    async fn preview_write(
+        &self,
+        Parameters(params): Parameters<PreviewWriteParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // A missing file previews as a diff against empty content
+        let current = match fs::read(&params.path).await {
+            Ok(bytes) => match String::from_utf8(bytes) {
+                Ok(current) => current,
+                Err(_) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INVALID_PARAMS,
+                        "Cannot diff a binary file".to_string(),
+                        None,
+                    ));
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let diff = unified_diff(&params.path, &current, &params.content);
+        if diff.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No changes to {}",
+                params.path
+            ))]));
+        }
+        Ok(CallToolResult::success(vec![Content::text(diff)]))
+    }
+
+    #[tool(
+        description = "Get the type, size, timestamps and permissions of a file or directory",
+        annotations(read_only_hint = true)
+    )]
+    async fn stat_file(
+        &self,
+        Parameters(params): Parameters<StatFileParams>,
+    ) -> Result<Json<StatFileOutput>, McpError> {
+        let metadata = if self.config.follow_symlinks {
+            fs::metadata(&params.path).await
+        } else {
+            fs::symlink_metadata(&params.path).await
+        };
+        match metadata {
+            Ok(metadata) => {
+                let file_type = if metadata.is_symlink() {
+                    "symlink"
+                } else if metadata.is_dir() {
+                    "directory"
+                } else {
+                    "file"
+                };
+                let target = if metadata.is_symlink() {
+                    fs::read_link(&params.path)
+                        .await
+                        .ok()
+                        .map(|target| target.to_string_lossy().into_owned())
+                } else {
+                    None
+                };
+
+                Ok(Json(StatFileOutput {
+                    path: params.path,
+                    file_type: file_type.to_string(),
+                    size: metadata.len(),
+                    modified: metadata.modified().ok().map(format_system_time),
+                    accessed: metadata.accessed().ok().map(format_system_time),
+                    created: metadata.created().ok().map(format_system_time),
+                    readonly: metadata.permissions().readonly(),
+                    target,
+                }))
+            }
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to stat file: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Set the modification time of a file to an RFC3339 timestamp or \"now\"",
+        annotations(
+            read_only_hint = false,
+            destructive_hint = false,
+            idempotent_hint = true
+        )
+    )]
+    async fn set_mtime(
+        &self,
+        Parameters(params): Parameters<SetMtimeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mtime = if params.mtime.eq_ignore_ascii_case("now") {
+            filetime::FileTime::now()
+        } else {
+            match chrono::DateTime::parse_from_rfc3339(&params.mtime) {
+                Ok(time) => filetime::FileTime::from_system_time(time.into()),
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INVALID_PARAMS,
+                        format!("Invalid RFC3339 timestamp: {}", e),
+                        None,
+                    ));
+                }
+            }
+        };
+
+        match filetime::set_file_mtime(&params.path, mtime) {
+            Ok(_) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Successfully set modification time of {} to {}",
+                params.path, params.mtime
+            ))])),
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to set modification time: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Cheaply classify a file by reading only its start: text or binary, MIME type, first line and shebang",
+        annotations(read_only_hint = true)
+    )]
+    async fn peek_file(
+        &self,
+        Parameters(params): Parameters<PeekFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut head = vec![0u8; PEEK_BYTES];
+        let bytes_read = match fs::File::open(&params.path).await {
+            Ok(mut file) => file.read(&mut head).await,
+            Err(e) => Err(e),
+        };
+        let bytes_read = match bytes_read {
+            Ok(bytes_read) => bytes_read,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+        head.truncate(bytes_read);
+
+        let mime = detect_mime(&head);
+        let is_binary = head.contains(&0) || mime != "text/plain";
+        let mut result = serde_json::json!({
+            "path": params.path,
+            "type": if is_binary { "binary" } else { "text" },
+            "mime": mime,
+        });
+        if !is_binary {
+            let text = String::from_utf8_lossy(&head);
+            let first_line = text.lines().next().unwrap_or_default();
+            result["firstLine"] = first_line.into();
+            if let Some(interpreter) = first_line.strip_prefix("#!") {
+                result["shebang"] = interpreter.trim().into();
+            }
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Split a file into numbered chunk files of a given number of bytes or lines",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn split_file(
+        &self,
+        Parameters(params): Parameters<SplitFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if params.chunk_size == 0 {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "chunk_size must be greater than 0".to_string(),
+                None,
+            ));
+        }
+        let Some(file_name) = std::path::Path::new(&params.path).file_name() else {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!("{} is not a file path", params.path),
+                None,
+            ));
+        };
+        let file_name = file_name.to_string_lossy().into_owned();
+        let output_dir = std::path::Path::new(&params.output_dir);
+        let mode = params.mode.unwrap_or(SplitMode::Bytes);
+        if let Ok(metadata) = fs::metadata(&params.path).await {
+            self.ensure_free_space(&params.output_dir, metadata.len())?;
+        }
+
+        let split = async {
+            // Pad the numbers to the widest one so the chunks sort in order by name
+            let units = match mode {
+                SplitMode::Bytes => fs::metadata(&params.path).await?.len(),
+                SplitMode::Lines => count_lines(&params.path).await? as u64,
+            };
+            let width = units.div_ceil(params.chunk_size).to_string().len().max(3);
+            fs::create_dir_all(output_dir).await?;
+            let mut reader = tokio::io::BufReader::new(fs::File::open(&params.path).await?);
+            let mut chunks = Vec::new();
+            // Only start a chunk while there is data left for it
+            while !reader.fill_buf().await?.is_empty() {
+                let chunk_path = output_dir.join(format!(
+                    "{}.{:0width$}",
+                    file_name,
+                    chunks.len() + 1,
+                    width = width
+                ));
+                let mut chunk = fs::File::create(&chunk_path).await?;
+                match mode {
+                    SplitMode::Bytes => {
+                        tokio::io::copy(&mut (&mut reader).take(params.chunk_size), &mut chunk)
+                            .await?;
+                    }
+                    SplitMode::Lines => {
+                        let mut line = Vec::new();
+                        for _ in 0..params.chunk_size {
+                            line.clear();
+                            if reader.read_until(b'\n', &mut line).await? == 0 {
+                                break;
+                            }
+                            chunk.write_all(&line).await?;
+                        }
+                    }
+                }
+                chunk.flush().await?;
+                chunks.push(chunk_path.to_string_lossy().into_owned());
+            }
+            Ok::<_, std::io::Error>(chunks)
+        };
+        let chunks = match split.await {
+            Ok(chunks) => chunks,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to split file: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let result = serde_json::json!({
+            "path": params.path,
+            "chunks": chunks,
+        });
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Concatenate files in order into an output file, such as chunks written by split_file",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn join_files(
+        &self,
+        Parameters(params): Parameters<JoinFilesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Check every input up front so a missing one does not leave a partial output
+        let output = fs::canonicalize(&params.output).await.ok();
+        let mut size = 0;
+        for path in &params.paths {
+            match fs::metadata(path).await {
+                Ok(metadata) if metadata.is_file() => size += metadata.len(),
+                Ok(_) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INVALID_PARAMS,
+                        format!("{} is not a file", path),
+                        None,
+                    ));
+                }
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INVALID_PARAMS,
+                        format!("Failed to read {}: {}", path, e),
+                        None,
+                    ));
+                }
+            }
+            if output.is_some() && fs::canonicalize(path).await.ok() == output {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("The output {} is also an input", params.output),
+                    None,
+                ));
+            }
+        }
+        self.ensure_free_space(&params.output, size)?;
+
+        let join = async {
+            let mut output = fs::File::create(&params.output).await?;
+            let mut total = 0;
+            for path in &params.paths {
+                total += tokio::io::copy(&mut fs::File::open(path).await?, &mut output).await?;
+            }
+            output.flush().await?;
+            Ok::<_, std::io::Error>(total)
+        };
+        match join.await {
+            Ok(total) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Successfully joined {} files into {} ({} bytes)",
+                params.paths.len(),
+                params.output,
+                total
+            ))])),
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to join files: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Check whether a file could be written, without creating or changing anything",
+        annotations(read_only_hint = true)
+    )]
+    async fn can_write(
+        &self,
+        Parameters(params): Parameters<CanWriteParams>,
+    ) -> Result<Json<CanWriteOutput>, McpError> {
+        let path = std::path::Path::new(&params.path);
+        let reason = match fs::metadata(path).await {
+            Ok(metadata) if metadata.is_dir() => Some("the path is a directory".to_string()),
+            Ok(metadata) if metadata.permissions().readonly() => {
+                Some("the file is read-only".to_string())
+            }
+            // Opening without create or truncate leaves the file untouched
+            Ok(_) => match fs::OpenOptions::new().write(true).open(path).await {
+                Ok(_) => None,
+                Err(e) => Some(format!("the file cannot be opened for writing: {}", e)),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let parent = path
+                    .parent()
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                    .unwrap_or(std::path::Path::new("."));
+                match fs::metadata(parent).await {
+                    Ok(metadata) if !metadata.is_dir() => Some(format!(
+                        "the parent {} is not a directory",
+                        parent.display()
+                    )),
+                    Ok(metadata) if metadata.permissions().readonly() => Some(format!(
+                        "the parent directory {} is read-only",
+                        parent.display()
+                    )),
+                    Ok(_) => None,
+                    Err(e) => Some(format!(
+                        "the parent directory {} is not accessible: {}",
+                        parent.display(),
+                        e
+                    )),
+                }
+            }
+            Err(e) => Some(format!("the path is not accessible: {}", e)),
+        };
+
+        Ok(Json(CanWriteOutput {
+            path: params.path,
+            writable: reason.is_none(),
+            reason,
+        }))
+    }
+
+    #[tool(
+        description = "Show a byte range of a file as a hex dump with offsets and ASCII, by default its first 256 bytes",
+        annotations(read_only_hint = true)
+    )]
+    async fn hexdump(
+        &self,
+        Parameters(params): Parameters<HexdumpParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let offset = params.offset.unwrap_or(0);
+        let length = params.length.unwrap_or(256).min(MAX_HEXDUMP_BYTES);
+
+        let read = async {
+            let mut file = fs::File::open(&params.path).await?;
+            file.seek(std::io::SeekFrom::Start(offset)).await?;
+            let mut bytes = Vec::with_capacity(length);
+            file.take(length as u64).read_to_end(&mut bytes).await?;
+            Ok::<_, std::io::Error>(bytes)
+        };
+        let bytes = match read.await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        if bytes.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No bytes at offset {} of {}",
+                offset, params.path
+            ))]));
+        }
+        Ok(CallToolResult::success(vec![Content::text(
+            format_hexdump(&bytes, offset),
+        )]))
+    }
+
+    #[tool(
+        description = "Check a file against size, extension, MIME type and format constraints and report which ones fail",
+        annotations(read_only_hint = true)
+    )]
+    async fn validate_file(
+        &self,
+        Parameters(params): Parameters<ValidateFileParams>,
+    ) -> Result<Json<ValidateFileOutput>, McpError> {
+        let mut output = ValidateFileOutput {
+            path: params.path.clone(),
+            valid: false,
+            failures: Vec::new(),
+            size: None,
+            mime: None,
+        };
+
+        let metadata = match fs::metadata(&params.path).await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => {
+                output.failures.push("the path is not a file".to_string());
+                return Ok(Json(output));
+            }
+            Err(e) => {
+                output.failures.push(format!("cannot read the file: {}", e));
+                return Ok(Json(output));
+            }
+        };
+        let size = metadata.len();
+        output.size = Some(size);
+
+        let too_large = params.max_size.is_some_and(|max_size| size > max_size);
+        if too_large {
+            output.failures.push(format!(
+                "size {} exceeds the maximum of {} bytes",
+                size,
+                params.max_size.unwrap_or_default()
+            ));
+        }
+
+        if let Some(extensions) = &params.extensions {
+            let extension = std::path::Path::new(&params.path)
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if !extensions
+                .iter()
+                .any(|expected| expected.trim_start_matches('.').to_lowercase() == extension)
+            {
+                output.failures.push(format!(
+                    "extension \"{}\" is not one of {}",
+                    extension,
+                    extensions.join(", ")
+                ));
+            }
+        }
+
+        let mut head = vec![0u8; PEEK_BYTES];
+        let bytes_read = match fs::File::open(&params.path).await {
+            Ok(mut file) => file.read(&mut head).await,
+            Err(e) => Err(e),
+        };
+        match bytes_read {
+            Ok(bytes_read) => {
+                head.truncate(bytes_read);
+                let mime = detect_mime(&head);
+                output.mime = Some(mime.to_string());
+                if let Some(expected) = &params.mime {
+                    let matches = match expected.strip_suffix("/*") {
+                        Some(kind) => mime.split('/').next() == Some(kind),
+                        None => mime.eq_ignore_ascii_case(expected),
+                    };
+                    if !matches {
+                        output
+                            .failures
+                            .push(format!("MIME type {} is not {}", mime, expected));
+                    }
+                }
+            }
+            Err(e) => output.failures.push(format!("cannot read the file: {}", e)),
+        }
+
+        // Oversized files are not parsed, that is what the size limit guards against
+        if let Some(format) = params.format
+            && !too_large
+        {
+            match fs::read_to_string(&params.path).await {
+                Ok(content) => {
+                    if let Err(e) = parse_config(&content, format) {
+                        output
+                            .failures
+                            .push(format!("content does not parse: {}", e));
+                    }
+                }
+                Err(e) => output.failures.push(format!("content is not text: {}", e)),
+            }
+        }
+
+        output.valid = output.failures.is_empty();
+        Ok(Json(output))
+    }
+
+    #[tool(
+        description = "Insert content at the beginning of a text file, returning the new size",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn prepend_file(
+        &self,
+        Parameters(params): Parameters<PrependFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        match is_binary_file(&params.path).await {
+            Ok(false) => {}
+            Ok(true) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "{} is a binary file, prepending text to it would corrupt it",
+                        params.path
+                    ),
+                    None,
+                ));
+            }
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        }
+
+        // The new copy is written in full before it replaces the original
+        let size = fs::metadata(&params.path).await.map_or(0, |m| m.len());
+        self.ensure_free_space(&params.path, size + params.content.len() as u64)?;
+        let original = match fs::File::open(&params.path).await {
+            Ok(file) => file,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+        // Stream the original after the new content so large files are not held in memory
+        let content = params.content.as_bytes().chain(original);
+        match replace_file_atomically(&params.path, content).await {
+            Ok(size) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Successfully prepended {} bytes to {}, new size {} bytes",
+                params.content.len(),
+                params.path,
+                size
+            ))])),
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to prepend to file: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Resolve a path through every symlink, listing each hop and the final absolute path, and reporting symlink cycles",
+        annotations(read_only_hint = true)
+    )]
+    async fn realpath(
+        &self,
+        Parameters(params): Parameters<RealpathParams>,
+    ) -> Result<Json<RealpathOutput>, McpError> {
+        resolve_symlinks(&params.path).await.map(Json)
+    }
+
+    #[tool(
+        description = "Truncate a file to zero length in place, keeping its permissions and the handles other processes hold, and report its previous size",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn clear_file(
+        &self,
+        Parameters(params): Parameters<ClearFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        match fs::metadata(&params.path).await {
+            Ok(metadata) if metadata.is_dir() => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("{} is a directory", params.path),
+                    None,
+                ));
+            }
+            _ => {}
+        }
+
+        // Truncating in place keeps the inode, so writers holding the file keep using it
+        let clear = async {
+            let file = fs::OpenOptions::new()
+                .write(true)
+                .open(&params.path)
+                .await?;
+            let previous_size = file.metadata().await?.len();
+            file.set_len(0).await?;
+            Ok::<_, std::io::Error>(previous_size)
+        };
+        match clear.await {
+            Ok(previous_size) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Cleared {} (previously {} bytes)",
+                params.path, previous_size
+            ))])),
+            Err(e) => Err(file_operation_error("clear file", &params.path, e)),
+        }
+    }
+
+    #[tool(
+        description = "Report the total, used and available space of the filesystem containing a path",
+        annotations(read_only_hint = true)
+    )]
+    async fn filesystem_stats(
+        &self,
+        Parameters(params): Parameters<FilesystemStatsParams>,
+    ) -> Result<Json<FilesystemStatsOutput>, McpError> {
+        match filesystem_stats_of(&params.path) {
+            Ok(stats) => {
+                let total = stats.total_space();
+                let used = total.saturating_sub(stats.free_space());
+                Ok(Json(FilesystemStatsOutput {
+                    path: params.path,
+                    total,
+                    used,
+                    available: stats.available_space(),
+                    free: stats.free_space(),
+                    used_percent: if total == 0 {
+                        0.0
+                    } else {
+                        (used as f64 / total as f64 * 10_000.0).round() / 100.0
+                    },
+                }))
+            }
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to read filesystem stats: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "List the drive letters on Windows or the mount points elsewhere, with their filesystem and space, as starting points for exploring the machine",
+        annotations(read_only_hint = true)
+    )]
+    async fn list_drives(
+        &self,
+        Parameters(params): Parameters<ListDrivesParams>,
+    ) -> Result<Json<ListDrivesOutput>, McpError> {
+        let mut drives = list_mount_points(params.include_virtual.unwrap_or(false)).await;
+        for drive in &mut drives {
+            if let Ok(stats) = fs4::statvfs(&drive.path) {
+                drive.total = Some(stats.total_space());
+                drive.available = Some(stats.available_space());
+            }
+        }
+        Ok(Json(ListDrivesOutput { drives }))
+    }
+
+    #[tool(
+        description = "Canonicalize a list of paths, resolving symlinks and .., and return the distinct paths and which inputs were duplicates",
+        annotations(read_only_hint = true)
+    )]
+    async fn dedup_paths(
+        &self,
+        Parameters(params): Parameters<DedupPathsParams>,
+    ) -> Result<Json<DedupPathsOutput>, McpError> {
+        let mut unique = Vec::new();
+        let mut sources: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut errors = BTreeMap::new();
+        for path in params.paths {
+            match resolve_symlinks(&path).await {
+                Ok(resolved) => {
+                    let sources = sources.entry(resolved.resolved.clone()).or_default();
+                    if sources.is_empty() {
+                        unique.push(resolved.resolved);
+                    }
+                    sources.push(path);
+                }
+                Err(e) => {
+                    errors.insert(path, e.message.into_owned());
+                }
+            }
+        }
+
+        Ok(Json(DedupPathsOutput {
+            unique,
+            duplicates: sources
+                .into_iter()
+                .filter(|(_, sources)| sources.len() > 1)
+                .collect(),
+            errors,
+        }))
+    }
 }
@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use tokio::{fs, io::AsyncReadExt};
+
+/// Digest algorithms available to the hashing tools
+#[derive(Debug, Clone, Copy, Default, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// The lowercase name of the algorithm, as accepted in parameters
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha384 => "sha384",
+            HashAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+/// Compute the hex-encoded SHA-256 digest of a file, reading it in chunks
+pub async fn hash_file(path: &Path) -> Result<String, std::io::Error> {
+    hash_file_with(path, HashAlgorithm::Sha256).await
+}
+
+/// Compute the hex-encoded digest of a file with the given algorithm
+pub async fn hash_file_with(
+    path: &Path,
+    algorithm: HashAlgorithm,
+) -> Result<String, std::io::Error> {
+    match algorithm {
+        HashAlgorithm::Sha256 => digest_file::<Sha256>(path).await,
+        HashAlgorithm::Sha384 => digest_file::<Sha384>(path).await,
+        HashAlgorithm::Sha512 => digest_file::<Sha512>(path).await,
+    }
+}
+
+async fn digest_file<D: Digest>(path: &Path) -> Result<String, std::io::Error> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let bytes_read = file.read(&mut buffer).await?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Hex-encode a digest
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,85 @@
+use std::{
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use rmcp::service::RunningServiceCancellationToken;
+
+use crate::service::DiveDefaultService;
+
+/// Tracks when the service last handled a tool call, to shut it down once it is idle
+pub struct ActivityTracker {
+    last_activity: Mutex<Instant>,
+    in_flight: AtomicUsize,
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        Self {
+            last_activity: Mutex::new(Instant::now()),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl ActivityTracker {
+    /// Mark a tool call as running until the returned guard is dropped
+    pub fn track(&self) -> ActivityGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        self.touch();
+        ActivityGuard(self)
+    }
+
+    fn touch(&self) {
+        if let Ok(mut last_activity) = self.last_activity.lock() {
+            *last_activity = Instant::now();
+        }
+    }
+
+    /// How long no tool call has been running
+    fn idle_for(&self) -> Duration {
+        if self.in_flight.load(Ordering::SeqCst) > 0 {
+            return Duration::ZERO;
+        }
+        self.last_activity
+            .lock()
+            .map(|last_activity| last_activity.elapsed())
+            .unwrap_or_default()
+    }
+}
+
+/// A running tool call, which ends when the guard is dropped
+pub struct ActivityGuard<'a>(&'a ActivityTracker);
+
+impl Drop for ActivityGuard<'_> {
+    fn drop(&mut self) {
+        self.0.touch();
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl DiveDefaultService {
+    /// Stop the running service once no tool call was handled for the configured idle
+    /// timeout. Returns right away when no idle timeout is configured.
+    pub async fn shutdown_when_idle(self, token: RunningServiceCancellationToken) {
+        let Some(idle_timeout_secs) = self.config.idle_timeout_secs else {
+            return;
+        };
+        let idle_timeout = Duration::from_secs(idle_timeout_secs);
+        loop {
+            let idle = self.activity.idle_for();
+            if idle >= idle_timeout {
+                eprintln!(
+                    "shutting down after {} seconds without tool calls",
+                    idle_timeout_secs
+                );
+                token.cancel();
+                return;
+            }
+            tokio::time::sleep(idle_timeout - idle).await;
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use rmcp::{
+    ErrorData as McpError,
+    model::{CallToolResult, Content},
+    tool, tool_router,
+};
+
+use crate::service::DiveDefaultService;
+
+#[tool_router(router = tool_router_introspect, vis = "pub")]
+impl DiveDefaultService {
+    #[tool(
+        description = "List every enabled tool with its group, description, input schema and whether it modifies its environment",
+        annotations(read_only_hint = true)
+    )]
+    async fn list_tool_metadata(&self) -> Result<CallToolResult, McpError> {
+        let groups = Self::tool_groups();
+
+        let mut tools = self.tool_router.list_all();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let metadata: serde_json::Value = tools
+            .iter()
+            .map(|tool| {
+                let group = groups
+                    .iter()
+                    .find(|(_, router)| router.has_route(&tool.name))
+                    .map(|(name, _)| *name);
+                // Tools without a read-only hint are assumed to modify their environment
+                let mutating = !tool
+                    .annotations
+                    .as_ref()
+                    .and_then(|annotations| annotations.read_only_hint)
+                    .unwrap_or(false);
+
+                serde_json::json!({
+                    "name": tool.name,
+                    "group": group,
+                    "description": tool.description,
+                    "mutating": mutating,
+                    "inputSchema": tool.input_schema,
+                })
+            })
+            .collect();
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&metadata).unwrap_or_else(|_| metadata.to_string()),
+        )]))
+    }
+}
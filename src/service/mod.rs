@@ -1,32 +1,123 @@
-use rmcp::{ServerHandler, handler::server::tool::ToolRouter, model::*, tool_handler, tool_router};
+use std::{collections::HashSet, sync::Arc};
 
+use rmcp::{
+    ErrorData as McpError, RoleServer, ServerHandler, handler::server::tool::ToolRouter, model::*,
+    service::RequestContext, tool_router,
+};
+
+mod budget;
+mod config;
+mod confirm;
+mod data;
+mod directory;
 mod echo;
 mod fetch;
 mod fs;
+mod hash;
+mod idle;
+mod introspect;
+mod quota;
+mod text;
+mod timeout;
+
+pub use config::ServiceConfig;
 
 #[derive(Clone)]
 pub struct DiveDefaultService {
+    config: Arc<ServiceConfig>,
     http_client: reqwest::Client,
+    /// Client skipping TLS certificate verification, only used when a fetch asks for it
+    insecure_http_client: reqwest::Client,
+    fetch_stats: Arc<fetch::FetchStats>,
+    host_limiter: Arc<fetch::HostLimiter>,
+    /// Headers added to every request of the fetch tools
+    default_headers: Arc<fetch::DefaultHeaders>,
+    /// Destructive calls held back until they are confirmed
+    pending_actions: Arc<confirm::PendingActions>,
+    /// Names of the tools annotated as destructive, which may need confirmation
+    destructive_tools: Arc<HashSet<String>>,
+    activity: Arc<idle::ActivityTracker>,
+    quotas: Arc<quota::ToolQuotas>,
     tool_router: ToolRouter<Self>,
 }
 
 #[tool_router]
 impl DiveDefaultService {
-    pub fn new() -> Self {
+    pub fn new(config: ServiceConfig) -> Self {
+        let tool_router = Self::tool_groups()
+            .into_iter()
+            .fold(ToolRouter::new(), |router, (_, group)| router + group);
         Self {
-            http_client: reqwest::Client::new(),
-            tool_router: Self::tool_router_echo()
-                + Self::tool_router_fetch()
-                + Self::tool_router_fs(),
+            host_limiter: Arc::new(fetch::HostLimiter::new(config.max_connections_per_host)),
+            quotas: Arc::new(quota::ToolQuotas::new(config.tool_quotas.clone())),
+            config: Arc::new(config),
+            http_client: fetch::build_http_client(false),
+            insecure_http_client: fetch::build_http_client(true),
+            fetch_stats: Arc::default(),
+            default_headers: Arc::default(),
+            pending_actions: Arc::default(),
+            destructive_tools: Arc::new(confirm::destructive_tools(&tool_router)),
+            activity: Arc::default(),
+            tool_router,
         }
     }
+
+    /// Tool routers of every tool group, paired with the group name
+    fn tool_groups() -> Vec<(&'static str, ToolRouter<Self>)> {
+        vec![
+            ("confirm", Self::tool_router_confirm()),
+            ("data", Self::tool_router_data()),
+            ("directory", Self::tool_router_directory()),
+            ("echo", Self::tool_router_echo()),
+            ("fetch", Self::tool_router_fetch()),
+            ("fs", Self::tool_router_fs()),
+            ("introspect", Self::tool_router_introspect()),
+            ("text", Self::tool_router_text()),
+        ]
+    }
+
+    /// Concurrency of a batching tool call, falling back to the service default
+    fn batch_concurrency(&self, requested: Option<usize>) -> usize {
+        requested.unwrap_or(self.config.default_concurrency).max(1)
+    }
 }
 
-#[tool_handler]
 impl ServerHandler for DiveDefaultService {
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let _activity = self.activity.track();
+        if self.requires_confirmation(&request.name) {
+            return Ok(self.hold_for_confirmation(request));
+        }
+        self.quotas.check(&request.name)?;
+        self.call_with_timeout(request, context)
+            .await
+            .map(|result| self.apply_output_budget(result))
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult::with_all_items(self.tool_router.list_all()))
+    }
+
     fn get_info(&self) -> ServerInfo {
+        let mut server_info = Implementation::from_build_env();
+        if let Some(name) = &self.config.server_name {
+            server_info.name = name.clone();
+        }
+        if let Some(version) = &self.config.server_version {
+            server_info.version = version.clone();
+        }
+
         ServerInfo {
-            instructions: Some("default mcp server for dive client".into()),
+            server_info,
+            instructions: Some(self.config.instructions.clone()),
             capabilities: ServerCapabilities::builder()
                 .enable_tools()
                 .enable_tool_list_changed()
@@ -0,0 +1,64 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use rmcp::ErrorData as McpError;
+
+/// Window over which tool quotas are counted
+const QUOTA_WINDOW: Duration = Duration::from_secs(60);
+
+/// Per-tool limits on the number of calls a minute, guarding against runaway loops such as
+/// deleting thousands of files
+pub struct ToolQuotas {
+    limits: HashMap<String, usize>,
+    calls: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl ToolQuotas {
+    pub fn new(limits: HashMap<String, usize>) -> Self {
+        Self {
+            limits,
+            calls: Mutex::default(),
+        }
+    }
+
+    /// Count a call of a tool, failing when it already used up its quota for the last minute
+    pub fn check(&self, name: &str) -> Result<(), McpError> {
+        let Some(&limit) = self.limits.get(name) else {
+            return Ok(());
+        };
+        // The timestamps stay consistent even if a holder panicked, so keep enforcing them
+        let mut calls = self
+            .calls
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let calls = calls.entry(name.to_string()).or_default();
+        let now = Instant::now();
+        while calls
+            .front()
+            .is_some_and(|&call| now.duration_since(call) >= QUOTA_WINDOW)
+        {
+            calls.pop_front();
+        }
+        if calls.len() >= limit {
+            let retry_in = calls
+                .front()
+                .map(|&oldest| QUOTA_WINDOW.saturating_sub(now.duration_since(oldest)))
+                .unwrap_or(QUOTA_WINDOW);
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_REQUEST,
+                format!(
+                    "Quota exceeded: {} is limited to {} calls a minute, try again in {} seconds",
+                    name,
+                    limit,
+                    retry_in.as_secs() + 1
+                ),
+                None,
+            ));
+        }
+        calls.push_back(now);
+        Ok(())
+    }
+}
@@ -0,0 +1,1528 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use rmcp::{
+    ErrorData as McpError, RoleServer,
+    handler::server::wrapper::{Json, Parameters},
+    model::{CallToolResult, Content},
+    service::RequestContext,
+    tool, tool_router,
+};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use crate::service::{
+    DiveDefaultService,
+    fetch::cancelled_result,
+    fs::{is_binary_file, replace_file_atomically},
+};
+
+#[derive(Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum FrequencyMode {
+    Words,
+    Lines,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct WordFrequencyParams {
+    /// The path to the text file to analyze
+    path: String,
+    /// Count words or whole lines (default words)
+    #[serde(default)]
+    mode: Option<FrequencyMode>,
+    /// Number of most frequent entries to return (default 20)
+    #[serde(default)]
+    top_n: Option<usize>,
+    /// Count entries case-insensitively (default true)
+    #[serde(default)]
+    case_fold: Option<bool>,
+    /// Skip common English stopwords, in words mode (default false)
+    #[serde(default)]
+    remove_stopwords: Option<bool>,
+}
+
+/// Common English words skipped by `word_frequency` when asked to
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "he",
+    "her", "his", "i", "if", "in", "is", "it", "its", "of", "on", "or", "she", "so", "that", "the",
+    "their", "them", "there", "they", "this", "to", "was", "we", "were", "what", "when", "which",
+    "who", "will", "with", "you", "your",
+];
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ParseLogParams {
+    /// The path to the log file to parse
+    path: String,
+    /// Regex matching the first line of an entry; its named groups (e.g. timestamp, level,
+    /// message) become the fields of the entry. Lines that do not match are appended to the
+    /// message of the previous entry. Defaults to lines like "2024-01-31 12:00:00 [INFO] text".
+    #[serde(default)]
+    pattern: Option<String>,
+    /// Only return entries whose level group is one of these, case-insensitively
+    #[serde(default)]
+    level_filter: Option<Vec<String>>,
+    /// Maximum number of entries to return (default 100)
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Clone, Copy, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct NormalizeLineEndingsParams {
+    /// The path to the text file to rewrite
+    path: String,
+    /// The line ending every line gets (lf or crlf)
+    target: LineEnding,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct InsertAtLineParams {
+    /// The path to the text file to edit
+    path: String,
+    /// The 1-based line number the content is inserted at, shifting that line and the ones
+    /// after it down. Beyond the end of the file the content is appended.
+    line: usize,
+    /// The content to insert as one or more lines
+    content: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct DeleteLinesParams {
+    /// The path to the text file to edit
+    path: String,
+    /// First 1-based line of the range to delete (default 1)
+    #[serde(default)]
+    start: Option<usize>,
+    /// Last 1-based line of the range to delete, inclusive (default the last line)
+    #[serde(default)]
+    end: Option<usize>,
+    /// Regex selecting the lines to delete, instead of a range
+    #[serde(default)]
+    pattern: Option<String>,
+    /// List the lines that would be deleted without changing the file (default false)
+    #[serde(default)]
+    dry_run: Option<bool>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ReadLinesParams {
+    /// The path to the text file to read
+    path: String,
+    /// The 1-based number of the first line to return (default 1)
+    #[serde(default)]
+    start_line: Option<usize>,
+    /// Maximum number of lines to return (default 1000)
+    #[serde(default)]
+    max_lines: Option<usize>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReadLinesOutput {
+    /// The file that was read
+    path: String,
+    /// The returned lines without their line endings
+    lines: Vec<String>,
+    /// The 1-based number of the first returned line; line i of lines is line start_line + i
+    start_line: usize,
+    /// Number of lines in the whole file
+    total_lines: usize,
+    /// Whether lines after the returned ones were left out
+    truncated: bool,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Transform {
+    /// Uppercase every line
+    Uppercase,
+    /// Lowercase every line
+    Lowercase,
+    /// Remove leading and trailing whitespace from every line
+    Trim,
+    /// Replace every match of a regex, referring to capture groups as $1 or ${name}
+    Replace {
+        pattern: String,
+        replacement: String,
+    },
+    /// Drop lines equal to the line before them
+    Dedup,
+    /// Sort the lines; this holds them in memory, unlike the other transforms
+    Sort {
+        #[serde(default)]
+        reverse: Option<bool>,
+    },
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct TransformFileParams {
+    /// The path to the text file to read
+    path: String,
+    /// The path to write the result to, which must differ from path
+    output_path: String,
+    /// Transforms applied to the lines in order, e.g. [{"op": "trim"}, {"op": "dedup"}]
+    transforms: Vec<Transform>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ReadCodeParams {
+    /// The path to the source file to read
+    path: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+struct ReadCodeOutput {
+    /// The file that was read
+    path: String,
+    /// Language of the file as a code block hint, such as rust or python, when it was
+    /// recognized by its name or shebang line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    /// The content of the file
+    content: String,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct TailFileParams {
+    /// The path to the text file to read
+    path: String,
+    /// Number of lines to return from the end of the file (default 10)
+    #[serde(default)]
+    lines: Option<usize>,
+    /// Keep watching the file and also return the lines appended to it, like `tail -f`
+    /// (default false)
+    #[serde(default)]
+    follow: Option<bool>,
+    /// How long to follow the file in milliseconds (default 5000, at most 300000)
+    #[serde(default)]
+    follow_ms: Option<u64>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct ReadSectionParams {
+    /// The path to the text file to read
+    path: String,
+    /// Text that opens the section, such as "## Usage" or "[server]"
+    start_marker: String,
+    /// Text that closes the section, searched for after the start marker
+    end_marker: String,
+    /// Treat both markers as regexes instead of literal text (default false)
+    #[serde(default)]
+    regex: Option<bool>,
+    /// Include the markers themselves in the returned content (default false)
+    #[serde(default)]
+    include_markers: Option<bool>,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReadSectionOutput {
+    /// The file that was read
+    path: String,
+    /// The text between the markers
+    content: String,
+    /// The 1-based line the content starts on
+    start_line: usize,
+    /// The 1-based line the content ends on
+    end_line: usize,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct DetectIndentationParams {
+    /// The path to the source file to inspect
+    path: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct DetectIndentationOutput {
+    /// The file that was inspected
+    path: String,
+    /// tabs, spaces, or none when no line is indented
+    style: String,
+    /// Spaces per indentation level, with spaces
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<usize>,
+    /// Share of the evidence agreeing with the result, from 0 to 1
+    confidence: f64,
+    /// Number of sampled lines indented with tabs
+    tab_lines: usize,
+    /// Number of sampled lines indented with spaces
+    space_lines: usize,
+    /// A few indented lines as they appear in the file
+    sample: Vec<String>,
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct TextAnalysisParams {
+    /// The path to the text file to analyze
+    path: String,
+}
+
+#[derive(Serialize, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct TextAnalysisOutput {
+    /// The file that was analyzed
+    path: String,
+    /// Number of characters, including whitespace
+    characters: usize,
+    /// Number of characters other than whitespace
+    characters_without_spaces: usize,
+    /// Number of words
+    words: usize,
+    /// Number of sentences, ended by '.', '!' or '?'
+    sentences: usize,
+    /// Number of paragraphs, separated by blank lines
+    paragraphs: usize,
+    /// Estimated number of syllables
+    syllables: usize,
+    average_words_per_sentence: f64,
+    average_syllables_per_word: f64,
+    /// Flesch reading ease; higher is easier, 60-70 is plain English
+    flesch_reading_ease: f64,
+    /// Flesch-Kincaid grade level, the US school grade needed to follow the text
+    flesch_kincaid_grade: f64,
+}
+
+/// A transform of `transform_file` ready to run on a line at a time
+enum LineStep {
+    Uppercase,
+    Lowercase,
+    Trim,
+    Replace(Regex, String),
+    Dedup(Option<String>),
+}
+
+impl LineStep {
+    /// Transform a line, or drop it by returning `None`
+    fn apply(&mut self, line: String) -> Option<String> {
+        match self {
+            LineStep::Uppercase => Some(line.to_uppercase()),
+            LineStep::Lowercase => Some(line.to_lowercase()),
+            LineStep::Trim => Some(line.trim().to_string()),
+            LineStep::Replace(pattern, replacement) => Some(
+                pattern
+                    .replace_all(&line, replacement.as_str())
+                    .into_owned(),
+            ),
+            LineStep::Dedup(previous) => {
+                if previous.as_deref() == Some(line.as_str()) {
+                    return None;
+                }
+                *previous = Some(line.clone());
+                Some(line)
+            }
+        }
+    }
+}
+
+/// Run a line through steps in order, stopping when one drops it
+fn apply_steps(steps: &mut [LineStep], line: String) -> Option<String> {
+    steps
+        .iter_mut()
+        .try_fold(line, |line, step| step.apply(line))
+}
+
+/// Languages by file extension, named as code block hints
+const EXTENSION_LANGUAGES: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("py", "python"),
+    ("pyi", "python"),
+    ("js", "javascript"),
+    ("mjs", "javascript"),
+    ("cjs", "javascript"),
+    ("jsx", "jsx"),
+    ("ts", "typescript"),
+    ("tsx", "tsx"),
+    ("go", "go"),
+    ("java", "java"),
+    ("kt", "kotlin"),
+    ("kts", "kotlin"),
+    ("scala", "scala"),
+    ("swift", "swift"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cc", "cpp"),
+    ("cpp", "cpp"),
+    ("cxx", "cpp"),
+    ("hpp", "cpp"),
+    ("cs", "csharp"),
+    ("rb", "ruby"),
+    ("php", "php"),
+    ("pl", "perl"),
+    ("lua", "lua"),
+    ("r", "r"),
+    ("dart", "dart"),
+    ("ex", "elixir"),
+    ("exs", "elixir"),
+    ("erl", "erlang"),
+    ("hs", "haskell"),
+    ("ml", "ocaml"),
+    ("clj", "clojure"),
+    ("zig", "zig"),
+    ("sh", "bash"),
+    ("bash", "bash"),
+    ("zsh", "zsh"),
+    ("fish", "fish"),
+    ("ps1", "powershell"),
+    ("bat", "batch"),
+    ("sql", "sql"),
+    ("html", "html"),
+    ("htm", "html"),
+    ("css", "css"),
+    ("scss", "scss"),
+    ("vue", "vue"),
+    ("svelte", "svelte"),
+    ("json", "json"),
+    ("yaml", "yaml"),
+    ("yml", "yaml"),
+    ("toml", "toml"),
+    ("xml", "xml"),
+    ("md", "markdown"),
+    ("proto", "protobuf"),
+    ("tf", "hcl"),
+];
+
+/// Languages of files recognized by their whole name
+const FILE_NAME_LANGUAGES: &[(&str, &str)] = &[
+    ("Dockerfile", "dockerfile"),
+    ("Makefile", "makefile"),
+    ("GNUmakefile", "makefile"),
+    ("CMakeLists.txt", "cmake"),
+    ("Rakefile", "ruby"),
+    ("Gemfile", "ruby"),
+    ("Jenkinsfile", "groovy"),
+];
+
+/// Languages by shebang interpreter, after stripping version numbers such as python3
+const INTERPRETER_LANGUAGES: &[(&str, &str)] = &[
+    ("sh", "bash"),
+    ("bash", "bash"),
+    ("dash", "bash"),
+    ("zsh", "zsh"),
+    ("fish", "fish"),
+    ("python", "python"),
+    ("node", "javascript"),
+    ("deno", "typescript"),
+    ("ruby", "ruby"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("Rscript", "r"),
+    ("pwsh", "powershell"),
+];
+
+/// Detect the language of a source file from its name, or else from a shebang line such as
+/// `#!/usr/bin/env python3`
+fn detect_language(path: &str, content: &str) -> Option<&'static str> {
+    let path = std::path::Path::new(path);
+    let name = path.file_name()?.to_str()?;
+    if let Some((_, language)) = FILE_NAME_LANGUAGES.iter().find(|(known, _)| *known == name) {
+        return Some(language);
+    }
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str())
+        && let Some((_, language)) = EXTENSION_LANGUAGES
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+    {
+        return Some(language);
+    }
+
+    let shebang = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = shebang.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        // Skip options of env such as -S
+        interpreter = words.find(|word| !word.starts_with('-'))?;
+    }
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETER_LANGUAGES
+        .iter()
+        .find(|(known, _)| *known == interpreter)
+        .map(|(_, language)| *language)
+}
+
+/// Estimate the syllables of an English word by its vowel groups, less a silent final 'e'
+fn count_syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+    let mut syllables = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            syllables += 1;
+        }
+        previous_vowel = vowel;
+    }
+    if word.ends_with('e') && !word.ends_with("le") && syllables > 1 {
+        syllables -= 1;
+    }
+    syllables.max(1)
+}
+
+/// Round a statistic to two decimals
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Lines listed by a dry run of `delete_lines`
+const MAX_DRY_RUN_LINES: usize = 100;
+
+/// Entry pattern of `parse_log` when no pattern is given
+const DEFAULT_LOG_PATTERN: &str = r"^(?P<timestamp>\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}\S*)\s+\[?(?P<level>[A-Za-z]+)\]?:?\s+(?P<message>.*)$";
+
+/// Read a text file, refusing binary files
+pub async fn read_text_file(path: &str) -> Result<String, McpError> {
+    match is_binary_file(path).await {
+        Ok(false) => {}
+        Ok(true) => {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!("{} is a binary file", path),
+                None,
+            ));
+        }
+        Err(e) => {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to read file: {}", e),
+                None,
+            ));
+        }
+    }
+
+    fs::read_to_string(path).await.map_err(|e| {
+        McpError::new(
+            rmcp::model::ErrorCode::INTERNAL_ERROR,
+            format!("Failed to read file: {}", e),
+            None,
+        )
+    })
+}
+
+/// Bytes read from the end of a file to find its last lines
+const TAIL_WINDOW_BYTES: u64 = 64 * 1024;
+
+/// The first and last `count` lines of a text file. The tail is empty when the head already
+/// holds the whole file, and only covers the final 64 KiB of very long lines.
+pub async fn head_and_tail_lines(
+    path: &str,
+    count: usize,
+) -> Result<(Vec<String>, Vec<String>), std::io::Error> {
+    let mut file = fs::File::open(path).await?;
+    let size = file.metadata().await?.len();
+
+    let mut reader = tokio::io::BufReader::new(&mut file);
+    let mut head = Vec::new();
+    let mut line = Vec::new();
+    let mut head_bytes = 0;
+    while head.len() < count {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line).await?;
+        if read == 0 {
+            break;
+        }
+        head_bytes += read as u64;
+        head.push(
+            String::from_utf8_lossy(&line)
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+        );
+    }
+    drop(reader);
+    if head_bytes >= size {
+        return Ok((head, Vec::new()));
+    }
+
+    // Start past the head so no line is reported twice
+    let start = size.saturating_sub(TAIL_WINDOW_BYTES).max(head_bytes);
+    file.seek(std::io::SeekFrom::Start(start)).await?;
+    let mut window = Vec::new();
+    file.read_to_end(&mut window).await?;
+    let window = String::from_utf8_lossy(&window);
+    let mut lines: Vec<&str> = window.lines().collect();
+    // A window starting mid-line begins with a partial line
+    if start > head_bytes && lines.len() > 1 {
+        lines.remove(0);
+    }
+    let tail = lines[lines.len().saturating_sub(count)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    Ok((head, tail))
+}
+
+/// Give every line of a text the target line ending; a last line without one keeps none.
+/// Returns the converted text, its number of lines and how many of them changed.
+pub fn convert_line_endings(content: &str, target: LineEnding) -> (String, usize, usize) {
+    let target = target.as_str();
+    let mut converted = String::with_capacity(content.len());
+    let mut lines = 0;
+    let mut changed = 0;
+    for line in content.split_inclusive('\n') {
+        lines += 1;
+        let (text, ending) = if let Some(text) = line.strip_suffix("\r\n") {
+            (text, "\r\n")
+        } else if let Some(text) = line.strip_suffix('\n') {
+            (text, "\n")
+        } else {
+            // The last line has no line ending to change
+            (line, "")
+        };
+        converted.push_str(text);
+        if !ending.is_empty() {
+            if ending != target {
+                changed += 1;
+            }
+            converted.push_str(target);
+        }
+    }
+    (converted, lines, changed)
+}
+
+/// Lines `detect_indentation` samples from the start of a file
+const INDENTATION_SAMPLE_LINES: usize = 10_000;
+
+/// Indented lines `detect_indentation` returns as a sample
+const INDENTATION_EXAMPLES: usize = 5;
+
+/// Longest `tail_file` follows a file
+const MAX_FOLLOW_MS: u64 = 300_000;
+
+/// Lines `tail_file` collects while following before it stops early
+const MAX_FOLLOW_LINES: usize = 10_000;
+
+/// The last `count` lines of a file, from its final 64 KiB only, and the size they end at
+async fn last_lines(path: &str, count: usize) -> Result<(Vec<String>, u64), std::io::Error> {
+    let mut file = fs::File::open(path).await?;
+    let size = file.metadata().await?.len();
+    let start = size.saturating_sub(TAIL_WINDOW_BYTES);
+    file.seek(std::io::SeekFrom::Start(start)).await?;
+    let mut window = Vec::new();
+    let end = start + file.read_to_end(&mut window).await? as u64;
+    let window = String::from_utf8_lossy(&window);
+    let mut lines: Vec<&str> = window.lines().collect();
+    // A window starting mid-line begins with a partial line
+    if start > 0 && lines.len() > 1 {
+        lines.remove(0);
+    }
+    let lines = lines[lines.len().saturating_sub(count)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    Ok((lines, end))
+}
+
+/// Count the lines of a file without holding it in memory; a last line without a line ending
+/// counts too
+pub async fn count_lines(path: &str) -> Result<usize, std::io::Error> {
+    let mut file = fs::File::open(path).await?;
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut lines = 0;
+    let mut last = b'\n';
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        lines += buffer[..read].iter().filter(|&&byte| byte == b'\n').count();
+        last = buffer[read - 1];
+    }
+    if last != b'\n' {
+        lines += 1;
+    }
+    Ok(lines)
+}
+
+#[tool_router(router = tool_router_text, vis = "pub")]
+impl DiveDefaultService {
+    #[tool(
+        description = "Count the most frequent words or lines in a text file",
+        annotations(read_only_hint = true)
+    )]
+    async fn word_frequency(
+        &self,
+        Parameters(params): Parameters<WordFrequencyParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let content = read_text_file(&params.path).await?;
+        let case_fold = params.case_fold.unwrap_or(true);
+        let remove_stopwords = params.remove_stopwords.unwrap_or(false);
+        let mode = params.mode.unwrap_or(FrequencyMode::Words);
+
+        let entries: Vec<&str> = match mode {
+            FrequencyMode::Words => content
+                .split(|c: char| !c.is_alphanumeric() && c != '\'')
+                .map(|word| word.trim_matches('\''))
+                .filter(|word| !word.is_empty())
+                .collect(),
+            FrequencyMode::Lines => content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect(),
+        };
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        let mut total = 0;
+        for entry in entries {
+            let entry = if case_fold {
+                entry.to_lowercase()
+            } else {
+                entry.to_string()
+            };
+            if remove_stopwords
+                && matches!(mode, FrequencyMode::Words)
+                && STOPWORDS.contains(&entry.to_lowercase().as_str())
+            {
+                continue;
+            }
+            *counts.entry(entry).or_default() += 1;
+            total += 1;
+        }
+
+        let unique = counts.len();
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(params.top_n.unwrap_or(20));
+
+        let result = serde_json::json!({
+            "path": params.path,
+            "total": total,
+            "unique": unique,
+            "top": counts
+                .iter()
+                .map(|(value, count)| serde_json::json!({ "value": value, "count": count }))
+                .collect::<Vec<_>>(),
+        });
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Parse a log file into structured entries using a regex with named groups",
+        annotations(read_only_hint = true)
+    )]
+    async fn parse_log(
+        &self,
+        Parameters(params): Parameters<ParseLogParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let pattern = match Regex::new(params.pattern.as_deref().unwrap_or(DEFAULT_LOG_PATTERN)) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid pattern: {}", e),
+                    None,
+                ));
+            }
+        };
+        let content = read_text_file(&params.path).await?;
+        let level_filter: Option<Vec<String>> = params
+            .level_filter
+            .map(|levels| levels.iter().map(|level| level.to_lowercase()).collect());
+        let limit = params.limit.unwrap_or(100);
+
+        let mut entries: Vec<serde_json::Map<String, serde_json::Value>> = Vec::new();
+        let mut unmatched_lines = 0;
+        for (index, line) in content.lines().enumerate() {
+            match pattern.captures(line) {
+                Some(captures) => {
+                    let mut entry = serde_json::Map::new();
+                    entry.insert("line".to_string(), (index + 1).into());
+                    for name in pattern.capture_names().flatten() {
+                        if let Some(value) = captures.name(name) {
+                            entry.insert(name.to_string(), value.as_str().into());
+                        }
+                    }
+                    entries.push(entry);
+                }
+                // Continuation lines, such as stack traces, belong to the previous entry
+                None => match entries
+                    .last_mut()
+                    .and_then(|entry| entry.get_mut("message"))
+                {
+                    Some(serde_json::Value::String(message)) => {
+                        message.push('\n');
+                        message.push_str(line);
+                    }
+                    _ => unmatched_lines += 1,
+                },
+            }
+        }
+
+        if let Some(levels) = &level_filter {
+            entries.retain(|entry| {
+                entry
+                    .get("level")
+                    .and_then(|level| level.as_str())
+                    .is_some_and(|level| levels.contains(&level.to_lowercase()))
+            });
+        }
+        let total_entries = entries.len();
+        entries.truncate(limit);
+
+        let result = serde_json::json!({
+            "path": params.path,
+            "entries": entries,
+            "totalEntries": total_entries,
+            "hasMore": total_entries > limit,
+            "unmatchedLines": unmatched_lines,
+        });
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Rewrite a text file so every line ends with LF or CRLF, reporting how many lines changed",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn normalize_line_endings(
+        &self,
+        Parameters(params): Parameters<NormalizeLineEndingsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let content = read_text_file(&params.path).await?;
+        let (normalized, lines, changed) = convert_line_endings(&content, params.target);
+
+        if changed > 0 {
+            self.ensure_free_space(&params.path, normalized.len() as u64)?;
+            if let Err(e) = replace_file_atomically(&params.path, normalized.as_bytes()).await {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to write file: {}", e),
+                    None,
+                ));
+            }
+        }
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Changed the line endings of {} of {} lines in {}",
+            changed, lines, params.path
+        ))]))
+    }
+
+    #[tool(
+        description = "Insert content as new lines at a 1-based line number of a text file, shifting the following lines down",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn insert_at_line(
+        &self,
+        Parameters(params): Parameters<InsertAtLineParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let content = read_text_file(&params.path).await?;
+        let ending = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let lines: Vec<&str> = content.split_inclusive('\n').collect();
+        let index = params.line.saturating_sub(1).min(lines.len());
+
+        let mut inserted = params.content;
+        if !inserted.ends_with('\n') {
+            inserted.push_str(ending);
+        }
+        let mut edited = String::with_capacity(content.len() + inserted.len() + ending.len());
+        for line in &lines[..index] {
+            edited.push_str(line);
+        }
+        // Appending after a last line without a line ending must not join the two
+        if !edited.is_empty() && !edited.ends_with('\n') {
+            edited.push_str(ending);
+        }
+        edited.push_str(&inserted);
+        for line in &lines[index..] {
+            edited.push_str(line);
+        }
+
+        self.ensure_free_space(&params.path, edited.len() as u64)?;
+        if let Err(e) = replace_file_atomically(&params.path, edited.as_bytes()).await {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to write file: {}", e),
+                None,
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Inserted {} lines at line {} of {}",
+            inserted.lines().count(),
+            index + 1,
+            params.path
+        ))]))
+    }
+
+    #[tool(
+        description = "Delete the lines of a text file in a line range or matching a regex, reporting how many were removed",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn delete_lines(
+        &self,
+        Parameters(params): Parameters<DeleteLinesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let has_range = params.start.is_some() || params.end.is_some();
+        let pattern = match (&params.pattern, has_range) {
+            (Some(pattern), false) => match Regex::new(pattern) {
+                Ok(pattern) => Some(pattern),
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INVALID_PARAMS,
+                        format!("Invalid pattern: {}", e),
+                        None,
+                    ));
+                }
+            },
+            (None, true) => None,
+            _ => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    "Provide either start/end or pattern".to_string(),
+                    None,
+                ));
+            }
+        };
+        let start = params.start.unwrap_or(1);
+        let end = params.end.unwrap_or(usize::MAX);
+        if start == 0 || end < start {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!("Invalid line range {}-{}", start, end),
+                None,
+            ));
+        }
+
+        let content = read_text_file(&params.path).await?;
+        let mut kept = String::with_capacity(content.len());
+        let mut removed = Vec::new();
+        for (index, line) in content.split_inclusive('\n').enumerate() {
+            let number = index + 1;
+            let text = line.trim_end_matches(['\r', '\n']);
+            let delete = match &pattern {
+                Some(pattern) => pattern.is_match(text),
+                None => (start..=end).contains(&number),
+            };
+            if delete {
+                removed.push((number, text));
+            } else {
+                kept.push_str(line);
+            }
+        }
+
+        if params.dry_run.unwrap_or(false) {
+            let mut message = format!("Would remove {} lines from {}", removed.len(), params.path);
+            for (number, text) in removed.iter().take(MAX_DRY_RUN_LINES) {
+                message.push_str(&format!("\n{}: {}", number, text));
+            }
+            if removed.len() > MAX_DRY_RUN_LINES {
+                message.push_str(&format!(
+                    "\n... and {} more",
+                    removed.len() - MAX_DRY_RUN_LINES
+                ));
+            }
+            return Ok(CallToolResult::success(vec![Content::text(message)]));
+        }
+
+        if !removed.is_empty() {
+            self.ensure_free_space(&params.path, kept.len() as u64)?;
+            if let Err(e) = replace_file_atomically(&params.path, kept.as_bytes()).await {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to write file: {}", e),
+                    None,
+                ));
+            }
+        }
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Removed {} lines from {}",
+            removed.len(),
+            params.path
+        ))]))
+    }
+
+    #[tool(
+        description = "Read a text file as numbered lines, returning the lines, the total line count and whether they were truncated",
+        annotations(read_only_hint = true)
+    )]
+    async fn read_lines(
+        &self,
+        Parameters(params): Parameters<ReadLinesParams>,
+    ) -> Result<Json<ReadLinesOutput>, McpError> {
+        match is_binary_file(&params.path).await {
+            Ok(false) => {}
+            Ok(true) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("{} is a binary file", params.path),
+                    None,
+                ));
+            }
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        }
+        let file = match fs::File::open(&params.path).await {
+            Ok(file) => file,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+        let start_line = params.start_line.unwrap_or(1).max(1);
+        let max_lines = params.max_lines.unwrap_or(1000);
+
+        // Keep reading past the page to count every line without holding the file
+        let mut reader = tokio::io::BufReader::new(file);
+        let mut lines = Vec::new();
+        let mut total_lines = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line).await {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    return Err(McpError::new(
+                        rmcp::model::ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to read file: {}", e),
+                        None,
+                    ));
+                }
+            }
+            total_lines += 1;
+            if total_lines >= start_line && lines.len() < max_lines {
+                lines.push(
+                    String::from_utf8_lossy(&line)
+                        .trim_end_matches(['\r', '\n'])
+                        .to_string(),
+                );
+            }
+        }
+
+        Ok(Json(ReadLinesOutput {
+            path: params.path,
+            truncated: start_line - 1 + lines.len() < total_lines,
+            lines,
+            start_line,
+            total_lines,
+        }))
+    }
+
+    #[tool(
+        description = "Stream a text file through an ordered list of line transforms (uppercase, lowercase, trim, replace, dedup, sort) into an output file",
+        annotations(read_only_hint = false, destructive_hint = true)
+    )]
+    async fn transform_file(
+        &self,
+        Parameters(params): Parameters<TransformFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        // Split the transforms into stages at each sort, which needs every line at once
+        let mut stages: Vec<(Vec<LineStep>, Option<bool>)> = vec![(Vec::new(), None)];
+        for transform in params.transforms {
+            let step = match transform {
+                Transform::Uppercase => LineStep::Uppercase,
+                Transform::Lowercase => LineStep::Lowercase,
+                Transform::Trim => LineStep::Trim,
+                Transform::Replace {
+                    pattern,
+                    replacement,
+                } => match Regex::new(&pattern) {
+                    Ok(pattern) => LineStep::Replace(pattern, replacement),
+                    Err(e) => {
+                        return Err(McpError::new(
+                            rmcp::model::ErrorCode::INVALID_PARAMS,
+                            format!("Invalid pattern: {}", e),
+                            None,
+                        ));
+                    }
+                },
+                Transform::Dedup => LineStep::Dedup(None),
+                Transform::Sort { reverse } => {
+                    if let Some(stage) = stages.last_mut() {
+                        stage.1 = Some(reverse.unwrap_or(false));
+                    }
+                    stages.push((Vec::new(), None));
+                    continue;
+                }
+            };
+            if let Some(stage) = stages.last_mut() {
+                stage.0.push(step);
+            }
+        }
+
+        match is_binary_file(&params.path).await {
+            Ok(false) => {}
+            Ok(true) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("{} is a binary file", params.path),
+                    None,
+                ));
+            }
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        }
+        let output = fs::canonicalize(&params.output_path).await.ok();
+        if output.is_some() && output == fs::canonicalize(&params.path).await.ok() {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                "output_path must differ from path".to_string(),
+                None,
+            ));
+        }
+        // The output is about as large as the input
+        if let Ok(metadata) = fs::metadata(&params.path).await {
+            self.ensure_free_space(&params.output_path, metadata.len())?;
+        }
+
+        let transform = async {
+            let input = fs::File::open(&params.path).await?;
+            let mut output =
+                tokio::io::BufWriter::new(fs::File::create(&params.output_path).await?);
+            let mut lines = tokio::io::BufReader::new(input).lines();
+            let (mut read, mut written) = (0, 0);
+
+            let (first_steps, first_sort) = &mut stages[0];
+            let mut buffered = Vec::new();
+            while let Some(line) = lines.next_line().await? {
+                read += 1;
+                let Some(line) = apply_steps(first_steps, line) else {
+                    continue;
+                };
+                if first_sort.is_some() {
+                    buffered.push(line);
+                } else {
+                    output.write_all(line.as_bytes()).await?;
+                    output.write_all(b"\n").await?;
+                    written += 1;
+                }
+            }
+
+            if first_sort.is_some() {
+                let mut sort = *first_sort;
+                for (steps, next_sort) in stages.iter_mut().skip(1) {
+                    if let Some(reverse) = sort {
+                        buffered.sort();
+                        if reverse {
+                            buffered.reverse();
+                        }
+                    }
+                    buffered = buffered
+                        .into_iter()
+                        .filter_map(|line| apply_steps(steps, line))
+                        .collect();
+                    sort = *next_sort;
+                }
+                for line in &buffered {
+                    output.write_all(line.as_bytes()).await?;
+                    output.write_all(b"\n").await?;
+                }
+                written = buffered.len();
+            }
+            output.flush().await?;
+            Ok::<_, std::io::Error>((read, written))
+        };
+        match transform.await {
+            Ok((read, written)) => Ok(CallToolResult::success(vec![Content::text(format!(
+                "Transformed {} lines of {} into {} lines in {}",
+                read, params.path, written, params.output_path
+            ))])),
+            Err(e) => Err(McpError::new(
+                rmcp::model::ErrorCode::INTERNAL_ERROR,
+                format!("Failed to transform file: {}", e),
+                None,
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Compute character, word and sentence counts and Flesch-Kincaid readability scores of a text file",
+        annotations(read_only_hint = true)
+    )]
+    async fn text_analysis(
+        &self,
+        Parameters(params): Parameters<TextAnalysisParams>,
+    ) -> Result<Json<TextAnalysisOutput>, McpError> {
+        let content = read_text_file(&params.path).await?;
+
+        let words: Vec<&str> = content
+            .split(|c: char| !c.is_alphanumeric() && c != '\'')
+            .map(|word| word.trim_matches('\''))
+            .filter(|word| word.chars().any(char::is_alphanumeric))
+            .collect();
+        let syllables: usize = words.iter().map(|word| count_syllables(word)).sum();
+
+        // A run of terminators ends one sentence; text without any is one sentence
+        let mut sentences = 0;
+        let mut in_terminators = false;
+        for c in content.chars() {
+            let terminator = matches!(c, '.' | '!' | '?');
+            if terminator && !in_terminators {
+                sentences += 1;
+            }
+            in_terminators = terminator;
+        }
+        if sentences == 0 && !words.is_empty() {
+            sentences = 1;
+        }
+
+        let paragraphs = content
+            .split("\n\n")
+            .filter(|paragraph| !paragraph.trim().is_empty())
+            .count();
+
+        let (words_per_sentence, syllables_per_word) = if words.is_empty() {
+            (0.0, 0.0)
+        } else {
+            (
+                words.len() as f64 / sentences as f64,
+                syllables as f64 / words.len() as f64,
+            )
+        };
+        let (reading_ease, grade) = if words.is_empty() {
+            (0.0, 0.0)
+        } else {
+            (
+                206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+                0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+            )
+        };
+
+        Ok(Json(TextAnalysisOutput {
+            path: params.path,
+            characters: content.chars().count(),
+            characters_without_spaces: content.chars().filter(|c| !c.is_whitespace()).count(),
+            words: words.len(),
+            sentences,
+            paragraphs,
+            syllables,
+            average_words_per_sentence: round2(words_per_sentence),
+            average_syllables_per_word: round2(syllables_per_word),
+            flesch_reading_ease: round2(reading_ease),
+            flesch_kincaid_grade: round2(grade),
+        }))
+    }
+
+    #[tool(
+        description = "Read a source file and detect its programming language from the file name or shebang line, as a hint for rendering it",
+        annotations(read_only_hint = true)
+    )]
+    async fn read_code(
+        &self,
+        Parameters(params): Parameters<ReadCodeParams>,
+    ) -> Result<Json<ReadCodeOutput>, McpError> {
+        if let Some(max_bytes) = self.config.max_read_bytes
+            && let Ok(metadata) = fs::metadata(&params.path).await
+            && metadata.len() > max_bytes
+        {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!(
+                    "{} is {} bytes, more than the {} bytes read at once; use read_lines",
+                    params.path,
+                    metadata.len(),
+                    max_bytes
+                ),
+                None,
+            ));
+        }
+        let content = read_text_file(&params.path).await?;
+        Ok(Json(ReadCodeOutput {
+            language: detect_language(&params.path, &content).map(str::to_string),
+            path: params.path,
+            content,
+        }))
+    }
+
+    #[tool(
+        description = "Return the last lines of a text file and, with follow, keep watching it for a bounded time and also return the lines appended meanwhile",
+        annotations(read_only_hint = true)
+    )]
+    async fn tail_file(
+        &self,
+        Parameters(params): Parameters<TailFileParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        match is_binary_file(&params.path).await {
+            Ok(false) => {}
+            Ok(true) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("{} is a binary file", params.path),
+                    None,
+                ));
+            }
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        }
+        let (lines, mut offset) = match last_lines(&params.path, params.lines.unwrap_or(10)).await {
+            Ok(tail) => tail,
+            Err(e) => {
+                return Err(McpError::new(
+                    rmcp::model::ErrorCode::INTERNAL_ERROR,
+                    format!("Failed to read file: {}", e),
+                    None,
+                ));
+            }
+        };
+
+        let mut result = serde_json::json!({
+            "path": params.path,
+            "lines": lines,
+        });
+        if params.follow.unwrap_or(false) {
+            let follow_ms = params.follow_ms.unwrap_or(5_000).min(MAX_FOLLOW_MS);
+            let deadline =
+                tokio::time::Instant::now() + std::time::Duration::from_millis(follow_ms);
+            let poll_interval = std::time::Duration::from_millis(250);
+            let mut new_lines: Vec<String> = Vec::new();
+            let mut partial = Vec::new();
+            let mut restarted = false;
+            while tokio::time::Instant::now() < deadline && new_lines.len() < MAX_FOLLOW_LINES {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(deadline.min(tokio::time::Instant::now() + poll_interval)) => {}
+                    _ = context.ct.cancelled() => return Ok(cancelled_result("Following the file")),
+                }
+                // A file that disappears, as in log rotation, is read again once it returns
+                let Ok(mut file) = fs::File::open(&params.path).await else {
+                    continue;
+                };
+                let size = file
+                    .metadata()
+                    .await
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0);
+                if size < offset {
+                    // The file was truncated or replaced, so read it from the start
+                    offset = 0;
+                    partial.clear();
+                    restarted = true;
+                }
+                if size == offset || file.seek(std::io::SeekFrom::Start(offset)).await.is_err() {
+                    continue;
+                }
+                let mut appended = Vec::new();
+                if (&mut file)
+                    .take(size - offset)
+                    .read_to_end(&mut appended)
+                    .await
+                    .is_err()
+                {
+                    continue;
+                }
+                offset += appended.len() as u64;
+                partial.extend_from_slice(&appended);
+                // Only complete lines are taken, the rest waits for its line ending
+                if let Some(end) = partial.iter().rposition(|&byte| byte == b'\n') {
+                    let complete: Vec<u8> = partial.drain(..=end).collect();
+                    new_lines.extend(
+                        String::from_utf8_lossy(&complete)
+                            .lines()
+                            .map(str::to_string),
+                    );
+                }
+            }
+            if !partial.is_empty() {
+                new_lines.push(String::from_utf8_lossy(&partial).to_string());
+            }
+            result["newLines"] = new_lines.into();
+            // Whether the file was truncated or replaced, so newLines start over from its top
+            result["restarted"] = restarted.into();
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )]))
+    }
+
+    #[tool(
+        description = "Return the part of a text file between a start and an end marker, literal or regex, such as one section of a config or markdown file",
+        annotations(read_only_hint = true)
+    )]
+    async fn read_section(
+        &self,
+        Parameters(params): Parameters<ReadSectionParams>,
+    ) -> Result<Json<ReadSectionOutput>, McpError> {
+        let compile = |marker: &str| {
+            let pattern = if params.regex.unwrap_or(false) {
+                marker.to_string()
+            } else {
+                regex::escape(marker)
+            };
+            Regex::new(&pattern).map_err(|e| {
+                McpError::new(
+                    rmcp::model::ErrorCode::INVALID_PARAMS,
+                    format!("Invalid marker {}: {}", marker, e),
+                    None,
+                )
+            })
+        };
+        let start_pattern = compile(&params.start_marker)?;
+        let end_pattern = compile(&params.end_marker)?;
+        let content = read_text_file(&params.path).await?;
+        let line_of = |offset: usize| content[..offset].matches('\n').count() + 1;
+
+        let Some(start) = start_pattern.find(&content) else {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!("start_marker not found in {}", params.path),
+                None,
+            ));
+        };
+        let Some(end) = end_pattern.find_at(&content, start.end()) else {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Unbalanced markers: start_marker on line {} has no end_marker after it",
+                    line_of(start.start())
+                ),
+                None,
+            ));
+        };
+        // With distinct markers, a second start before the end means the section never closed
+        if params.start_marker != params.end_marker
+            && let Some(again) = start_pattern.find_at(&content, start.end())
+            && again.end() <= end.start()
+        {
+            return Err(McpError::new(
+                rmcp::model::ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Unbalanced markers: start_marker on line {} opens again on line {} before end_marker",
+                    line_of(start.start()),
+                    line_of(again.start())
+                ),
+                None,
+            ));
+        }
+
+        let (from, to) = if params.include_markers.unwrap_or(false) {
+            (start.start(), end.end())
+        } else {
+            (start.end(), end.start())
+        };
+        // A section ending in a line ending ends on the line before the end marker
+        let last = if content[from..to].ends_with('\n') {
+            to - 1
+        } else {
+            to
+        };
+        Ok(Json(ReadSectionOutput {
+            content: content[from..to].to_string(),
+            start_line: line_of(from),
+            end_line: line_of(last),
+            path: params.path,
+        }))
+    }
+
+    #[tool(
+        description = "Detect whether a source file indents with tabs or spaces and how many spaces make one level, with a confidence and a few sample lines",
+        annotations(read_only_hint = true)
+    )]
+    async fn detect_indentation(
+        &self,
+        Parameters(params): Parameters<DetectIndentationParams>,
+    ) -> Result<Json<DetectIndentationOutput>, McpError> {
+        let content = read_text_file(&params.path).await?;
+
+        let mut tab_lines = 0;
+        let mut space_lines = 0;
+        let mut sample = Vec::new();
+        // How often each increase of space indentation occurs between consecutive lines
+        let mut increases: HashMap<usize, usize> = HashMap::new();
+        let mut previous_spaces = 0;
+        for line in content.lines().take(INDENTATION_SAMPLE_LINES) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            if line.starts_with('\t') {
+                tab_lines += 1;
+            } else if spaces > 0 {
+                space_lines += 1;
+                if spaces > previous_spaces {
+                    *increases.entry(spaces - previous_spaces).or_default() += 1;
+                }
+            }
+            if !line.starts_with('\t') {
+                previous_spaces = spaces;
+            }
+            if (line.starts_with('\t') || spaces > 0) && sample.len() < INDENTATION_EXAMPLES {
+                sample.push(line.to_string());
+            }
+        }
+
+        let indented = tab_lines + space_lines;
+        let (style, width, confidence) = if indented == 0 {
+            ("none", None, 1.0)
+        } else if tab_lines > space_lines {
+            ("tabs", None, tab_lines as f64 / indented as f64)
+        } else {
+            // Increases of one space are mostly alignment, not indentation
+            let total: usize = increases
+                .iter()
+                .filter(|(width, _)| **width > 1)
+                .map(|(_, count)| count)
+                .sum();
+            let width = increases
+                .iter()
+                .filter(|(width, _)| **width > 1)
+                .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(width, count)| (*width, *count));
+            let style_share = space_lines as f64 / indented as f64;
+            match width {
+                Some((width, count)) => (
+                    "spaces",
+                    Some(width),
+                    style_share * count as f64 / total as f64,
+                ),
+                None => ("spaces", None, style_share),
+            }
+        };
+
+        Ok(Json(DetectIndentationOutput {
+            path: params.path,
+            style: style.to_string(),
+            width,
+            confidence: round2(confidence),
+            tab_lines,
+            space_lines,
+            sample,
+        }))
+    }
+}
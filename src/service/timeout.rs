@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use rmcp::{
+    ErrorData as McpError, RoleServer,
+    handler::server::tool::ToolCallContext,
+    model::{CallToolRequestParam, CallToolResult, Content},
+    service::RequestContext,
+};
+
+use crate::service::DiveDefaultService;
+
+impl DiveDefaultService {
+    /// Run a tool call through the router, giving up once the tool's configured timeout or
+    /// the default one passes. A call that runs out of time is dropped and reported as a
+    /// failed tool result.
+    pub async fn call_with_timeout(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let timeout_ms = self
+            .config
+            .tool_timeouts_ms
+            .get(request.name.as_ref())
+            .copied()
+            .or(self.config.default_tool_timeout_ms);
+        let name = request.name.clone();
+        let call = self
+            .tool_router
+            .call(ToolCallContext::new(self, request, context));
+        let Some(timeout_ms) = timeout_ms else {
+            return call.await;
+        };
+        match tokio::time::timeout(Duration::from_millis(timeout_ms), call).await {
+            Ok(result) => result,
+            Err(_) => Ok(CallToolResult::error(vec![Content::text(format!(
+                "{} timed out after {} ms",
+                name, timeout_ms
+            ))])),
+        }
+    }
+}